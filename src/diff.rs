@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use crate::focus::{Focus, FocusLocator, CircularZeroIndex};
+use crate::node::{NodeValue, ListValue, MapValue};
+
+
+/// 两个版本之间的一处差异，focus为该差异在旧版本中的位置。
+/// 列表的插入和删除按顺序依次应用时下标有效。
+#[derive(Debug, Clone)]
+pub enum DiffOp {
+    Added {
+        focus: Arc<Focus>,
+        value: Arc<NodeValue>,
+    },
+    Removed {
+        focus: Arc<Focus>,
+        value: Arc<NodeValue>,
+    },
+    Replaced {
+        focus: Arc<Focus>,
+        old_value: Arc<NodeValue>,
+        new_value: Arc<NodeValue>,
+    },
+    ListInserted {
+        focus: Arc<Focus>,
+        value: Arc<NodeValue>,
+    },
+    ListDeleted {
+        focus: Arc<Focus>,
+        value: Arc<NodeValue>,
+    },
+}
+
+impl DiffOp {
+    pub fn focus(&self) -> &Arc<Focus> {
+        match self {
+            DiffOp::Added { focus, .. } => focus,
+            DiffOp::Removed { focus, .. } => focus,
+            DiffOp::Replaced { focus, .. } => focus,
+            DiffOp::ListInserted { focus, .. } => focus,
+            DiffOp::ListDeleted { focus, .. } => focus,
+        }
+    }
+}
+
+
+/// 比较old和new两棵子树，将差异追加到ops。
+/// 相同的Arc子树（结构共享）直接跳过，不再展开。
+pub(crate) fn diff_nodes(
+    focus: &Arc<Focus>,
+    old_node: &Arc<NodeValue>,
+    new_node: &Arc<NodeValue>,
+    ops: &mut Vec<DiffOp>,
+) {
+    if Arc::ptr_eq(old_node, new_node) {
+        return;
+    }
+
+    match (old_node.as_ref(), new_node.as_ref()) {
+        (NodeValue::Map(old_map), NodeValue::Map(new_map)) => {
+            diff_maps(focus, old_map, new_map, ops)
+        },
+        (NodeValue::List(old_list), NodeValue::List(new_list)) => {
+            diff_lists(focus, old_list, new_list, ops)
+        },
+        _ => {
+            if !old_node.deep_eq(new_node) {
+                ops.push(DiffOp::Replaced {
+                    focus: focus.clone(),
+                    old_value: old_node.clone(),
+                    new_value: new_node.clone(),
+                });
+            }
+        },
+    }
+}
+
+fn diff_maps(
+    focus: &Arc<Focus>,
+    old_map: &MapValue,
+    new_map: &MapValue,
+    ops: &mut Vec<DiffOp>,
+) {
+    if old_map.map.ptr_eq(&new_map.map) {
+        return;
+    }
+
+    // 按key排序，保证差异的顺序稳定
+    let mut keys = old_map.map.keys()
+        .chain(new_map.map.keys().filter(|key| !old_map.contains_key(key)))
+        .collect::<Vec<&String>>();
+    keys.sort();
+
+    for key in keys {
+        match (old_map.get_item(key), new_map.get_item(key)) {
+            (Some(old_item), Some(new_item)) => {
+                if !Arc::ptr_eq(old_item, new_item) {
+                    diff_nodes(&focus.focus(key.as_str()), old_item, new_item, ops);
+                }
+            },
+            (Some(old_item), None) => {
+                ops.push(DiffOp::Removed {
+                    focus: focus.focus(key.as_str()),
+                    value: old_item.clone(),
+                });
+            },
+            (None, Some(new_item)) => {
+                ops.push(DiffOp::Added {
+                    focus: focus.focus(key.as_str()),
+                    value: new_item.clone(),
+                });
+            },
+            (None, None) => {},
+        }
+    }
+}
+
+fn diff_lists(
+    focus: &Arc<Focus>,
+    old_list: &ListValue,
+    new_list: &ListValue,
+    ops: &mut Vec<DiffOp>,
+) {
+    if old_list.list.ptr_eq(&new_list.list) {
+        return;
+    }
+
+    let same = |a: &Arc<NodeValue>, b: &Arc<NodeValue>| Arc::ptr_eq(a, b) || a.deep_eq(b);
+
+    let old_len = old_list.list.len();
+    let new_len = new_list.list.len();
+
+    // 去掉相同的头部和尾部，只比较中间变化的部分
+    let mut head = 0;
+    while head < old_len && head < new_len && same(&old_list.list[head], &new_list.list[head]) {
+        head += 1;
+    }
+
+    let mut tail = 0;
+    while tail < old_len - head && tail < new_len - head
+        && same(&old_list.list[old_len - tail - 1], &new_list.list[new_len - tail - 1]) {
+        tail += 1;
+    }
+
+    let old_count = old_len - head - tail;
+    let new_count = new_len - head - tail;
+    let paired = old_count.min(new_count);
+
+    for offset in 0..paired {
+        let index = head + offset;
+        diff_nodes(
+            &focus.focus(index as CircularZeroIndex),
+            &old_list.list[index],
+            &new_list.list[index],
+            ops,
+        );
+    }
+
+    // 从后向前删除，前面的下标不受影响
+    for index in (head + paired..head + old_count).rev() {
+        ops.push(DiffOp::ListDeleted {
+            focus: focus.focus(index as CircularZeroIndex),
+            value: old_list.list[index].clone(),
+        });
+    }
+
+    for index in head + paired..head + new_count {
+        ops.push(DiffOp::ListInserted {
+            focus: focus.focus(index as CircularZeroIndex),
+            value: new_list.list[index].clone(),
+        });
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::error::Error;
+use crate::focus::{Focus, FocusLocator};
+use crate::node::NodeValue;
+
+use super::cone::{Cone, get_item_node};
+use super::domain::Domain;
+
+
+/// 默认最多缓存的位置个数
+const DEFAULT_CAPACITY: usize = 4096;
+
+struct CacheEntry {
+    node: Arc<NodeValue>,
+    used: AtomicU64, // 最近一次读取时的tick
+}
+
+/// focus到节点的缓存，与已提交的根节点一致。
+/// 某个focus上记录的变更提交时，它的祖先和后代的缓存失效，其他位置不受影响。
+/// 超过容量时移除最久没有读取的位置
+pub struct NodeCache {
+    entries: RwLock<HashMap<Arc<Focus>, CacheEntry>>,
+    dirty: Mutex<Vec<Arc<Focus>>>, // 已记录但尚未提交的变更的位置
+    generation: AtomicU64, // 每次失效加一，查找期间发生过失效的结果不写入缓存
+    capacity: AtomicUsize,
+    tick: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// 缓存的命中统计
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+#[allow(clippy::mutable_key_type)] // Focus按指针比较和哈希
+impl NodeCache {
+    pub(crate) fn new() -> Self {
+        NodeCache {
+            entries: RwLock::new(HashMap::new()),
+            dirty: Mutex::new(Vec::new()),
+            generation: AtomicU64::new(0),
+            capacity: AtomicUsize::new(DEFAULT_CAPACITY),
+            tick: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn get(&self, focus: &Arc<Focus>) -> Option<Arc<NodeValue>> {
+        let entries = self.entries.read().unwrap();
+        match entries.get(focus) {
+            Some(entry) => {
+                entry.used.store(self.next_tick(), Ordering::Relaxed);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.node.clone())
+            },
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            },
+        }
+    }
+
+    fn insert(&self, generation: u64, focus: &Arc<Focus>, node: Arc<NodeValue>) {
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        if capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.write().unwrap();
+        if self.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+
+        if entries.len() >= capacity && !entries.contains_key(focus) {
+            // 一次移除四分之一，不必每次写入都查找最久未读的位置
+            evict(&mut entries, capacity - capacity / 4 - 1);
+        }
+        let used = AtomicU64::new(self.next_tick());
+        entries.insert(focus.clone(), CacheEntry { node, used });
+    }
+
+    #[inline]
+    fn next_tick(&self) -> u64 {
+        self.tick.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// 最多缓存capacity个位置，为0时不缓存
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        let mut entries = self.entries.write().unwrap();
+        self.capacity.store(capacity, Ordering::Relaxed);
+        if entries.len() > capacity {
+            evict(&mut entries, capacity);
+        }
+    }
+
+    /// focus处记录了变更，提交时它的缓存失效
+    pub(crate) fn mark_dirty(&self, focus: &Arc<Focus>) {
+        self.dirty.lock().unwrap().push(focus.clone());
+    }
+
+    /// 变更已提交，使记录过变更的位置失效
+    pub(crate) fn flush(&self) {
+        let dirty = std::mem::take(&mut *self.dirty.lock().unwrap());
+        for focus in &dirty {
+            self.invalidate(focus);
+        }
+    }
+
+    /// focus处的节点已改变，它的祖先和后代随之改变
+    fn invalidate(&self, focus: &Arc<Focus>) {
+        let mut entries = self.entries.write().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+
+        if entries.is_empty() {
+            return;
+        }
+
+        if focus.get_parent().is_none() {
+            entries.clear();
+            return;
+        }
+
+        entries.retain(|cached, _| !is_related(cached, focus));
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.read().unwrap().len(),
+            capacity: self.capacity.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 只保留最近读取的keep个位置
+#[allow(clippy::mutable_key_type)]
+fn evict(entries: &mut HashMap<Arc<Focus>, CacheEntry>, keep: usize) {
+    if keep == 0 {
+        entries.clear();
+        return;
+    }
+
+    let mut used = entries.values()
+        .map(|entry| entry.used.load(Ordering::Relaxed))
+        .collect::<Vec<u64>>();
+    let index = used.len() - keep;
+    let (_, threshold, _) = used.select_nth_unstable(index);
+    let threshold = *threshold;
+
+    entries.retain(|_, entry| entry.used.load(Ordering::Relaxed) >= threshold);
+}
+
+/// a与b相同，或其中一个是另一个的祖先
+fn is_related(a: &Arc<Focus>, b: &Arc<Focus>) -> bool {
+    is_ancestor_or_self(a, b) || is_ancestor_or_self(b, a)
+}
+
+pub(super) fn is_ancestor_or_self(ancestor: &Arc<Focus>, focus: &Arc<Focus>) -> bool {
+    let mut current = Some(focus);
+    while let Some(node) = current {
+        if Arc::ptr_eq(node, ancestor) {
+            return true;
+        }
+        current = node.get_parent();
+    }
+    false
+}
+
+impl Cone {
+    /// 取得focus处的节点。只有读取已提交的根节点时才使用缓存，
+    /// 持有写入锁的线程读到的是尚未提交的根节点
+    pub(crate) fn focus_node(&self, focus: &Arc<Focus>) -> Result<Arc<NodeValue>, Error> {
+        let parent_focus = match focus.get_parent() {
+            Some(parent_focus) => parent_focus,
+            None => return Ok(self.visible_root()),
+        };
+
+        // 先取得generation再读取根节点：提交时先换根节点再使缓存失效，
+        // 因此读自旧根节点的结果要么随后被移除，要么不会被写入缓存
+        let generation = self.node_cache.generation.load(Ordering::SeqCst);
+
+        if self.is_writer() {
+            let parent_node = self.focus_node(parent_focus)?;
+            return get_item_node(parent_focus, &parent_node, &focus.get_access_key());
+        }
+
+        if let Some(node) = self.node_cache.get(focus) {
+            return Ok(node);
+        }
+
+        let parent_node = self.focus_node(parent_focus)?;
+        let node = get_item_node(parent_focus, &parent_node, &focus.get_access_key())?;
+        self.node_cache.insert(generation, focus, node.clone());
+        Ok(node)
+    }
+}
+
+impl Domain {
+    /// 路径解析缓存的命中统计
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cone.node_cache.stats()
+    }
+
+    /// 路径解析缓存最多保留的位置个数，默认4096，为0时不缓存。
+    /// 超出时移除最久没有读取的位置
+    pub fn set_cache_capacity(&self, capacity: usize) {
+        self.cone.node_cache.set_capacity(capacity);
+    }
+}
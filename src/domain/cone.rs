@@ -1,54 +1,145 @@
-use std::sync::Arc;
-use std::cell::RefCell;
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::sync::atomic::AtomicU64;
+use std::collections::VecDeque;
+use std::thread::ThreadId;
+use arc_swap::ArcSwap;
 use crate::focus::{AccessKey, Focus, FocusLocator};
 use crate::node::NodeValue;
 use crate::error::Error;
 
 use super::log::ChangeLogger;
+use super::undo::UndoHistory;
+use super::subscribe::Notifier;
+use super::cache::NodeCache;
+use super::conflict::ConflictPolicy;
+use super::stamp::VersionStamps;
 
 pub struct Cone {
     pub logger: ChangeLogger,
-    pub root_node: RefCell<Arc<NodeValue>>,
-    pub root_focus: Arc<Focus>
+    pub root_node: ArcSwap<NodeValue>,
+    pub committed_root: ArcSwap<NodeValue>, // 事务之外可见的根节点
+    pub root_focus: Arc<Focus>,
+    pub versions: RwLock<VecDeque<(u64, Arc<NodeValue>)>>, // 按txid递增的历史根节点
+    pub version_limit: RwLock<Option<usize>>,
+    pub undo_history: RwLock<UndoHistory>,
+    pub writer: Mutex<Option<(ThreadId, usize)>>, // 持有写入锁的线程及重入次数
+    pub writer_released: Condvar,
+    pub(crate) writer_thread: AtomicU64, // 持有写入锁的线程编号，读取时无锁判断
+    pub notifier: Notifier,
+    pub node_cache: NodeCache,
+    pub conflict_policy: RwLock<ConflictPolicy>,
+    pub(crate) stamps: VersionStamps,
 }
 
 impl Cone {
     pub fn new() -> Arc<Cone> {
+        let root_node = Arc::new(NodeValue::None);
+
         Arc::new(Cone {
             logger: ChangeLogger::new(),
-            root_node: RefCell::new(Arc::new(NodeValue::None)),
-            root_focus: Focus::new()
+            root_node: ArcSwap::new(root_node.clone()),
+            committed_root: ArcSwap::new(root_node.clone()),
+            root_focus: Focus::new(),
+            versions: RwLock::new(vec![(0, root_node)].into()),
+            version_limit: RwLock::new(None),
+            undo_history: RwLock::new(UndoHistory::new()),
+            writer: Mutex::new(None),
+            writer_released: Condvar::new(),
+            writer_thread: AtomicU64::new(0),
+            notifier: Notifier::new(),
+            node_cache: NodeCache::new(),
+            conflict_policy: RwLock::new(ConflictPolicy::default()),
+            stamps: VersionStamps::new(),
         })
     }
 
-    /// 取得focus对应的NodeValue，从root开始层层查找
+    /// 以root为根节点的独立cone，不记录历史
+    pub(crate) fn detached(root: Arc<NodeValue>) -> Arc<Cone> {
+        let cone = Cone::new();
+        cone.root_node.store(root.clone());
+        cone.committed_root.store(root);
+        *cone.version_limit.write().unwrap() = Some(0);
+        cone.versions.write().unwrap().clear();
+        cone
+    }
+
+    /// 取得focus对应的NodeValue，从root开始层层查找。
+    /// 持有写入锁的线程先合并尚未处理的更新，之前的写入总能被读到
     pub(crate) fn get_focus_node<'a>(
         &self, 
         focus: &'a Arc<Focus>
     ) -> Result<(Option<Arc<NodeValue>>, Arc<NodeValue>), Error> {
 
-        // TODO 使用cache机制以便加速访问
+        self.solve_pending_at(focus);
+
         if let Some(ref parent_focus) = focus.parent_focus {
-            let (_, parent_node) = self.get_focus_node(parent_focus)?;
-            let item_node = get_item_node(&parent_focus, &parent_node, &focus.get_access_key())?;
+            let parent_node = self.focus_node(parent_focus)?;
+            let item_node = self.focus_node(focus)?;
             Ok((Some(parent_node), item_node))
         } else {
-            let root_node = self.root_node.borrow().clone();
-            Ok((None, root_node))
+            Ok((None, self.visible_root()))
         }
     }
 
     /// 设置该domain的root节点
     pub(crate) fn remount_root(&self, new_root: Arc<NodeValue>) {
-        let mut root_node = self.root_node.borrow_mut();
-        *root_node = new_root;
+        self.root_node.store(new_root);
+
+        // 事务中的根节点在提交时才对其他线程可见，并成为一个版本
+        if self.logger.transaction.read().unwrap().is_none() {
+            self.commit_root();
+        }
     } 
 
+    pub(crate) fn commit_root(&self) {
+        self.committed_root.store(self.root_node.load_full());
+        // 已提交的根节点换过之后，缓存中读自旧根节点的项才失效
+        self.node_cache.flush();
+        self.stamps.commit();
+        self.record_version();
+    }
+
+    /// 当前线程可见的根节点：持有写入锁的线程读取自己的根节点，
+    /// 其他线程只读取已提交的根节点，尚未提交的修改不可见
+    pub(crate) fn visible_root(&self) -> Arc<NodeValue> {
+        if self.is_writer() {
+            self.root_node.load_full()
+        } else {
+            self.committed_root.load_full()
+        }
+    }
+
+    /// 将当前根节点记为当前txid的版本，同一txid只保留最后的根节点
+    pub(crate) fn record_version(&self) {
+        let txid = self.logger.current_txid();
+        let root_node = self.root_node.load_full();
+
+        let mut versions = self.versions.write().unwrap();
+        if let Some((last_txid, last_root)) = versions.back() {
+            if Arc::ptr_eq(last_root, &root_node) {
+                return;
+            }
+            if *last_txid == txid {
+                versions.pop_back();
+            } else {
+                let limit = *self.version_limit.read().unwrap();
+                self.undo_history.write().unwrap().record(last_root.clone(), limit);
+            }
+        }
+        versions.push_back((txid, root_node));
+
+        if let Some(limit) = *self.version_limit.read().unwrap() {
+            while versions.len() > limit {
+                versions.pop_front();
+            }
+        }
+    }
+
     /// 取得根节点
     #[inline]
     pub(crate) fn get_root_node(&self) -> Arc<NodeValue> {
         self.solve_pending_at(&self.root_focus);
-        self.root_node.borrow().clone()
+        self.visible_root()
     }
 }
 
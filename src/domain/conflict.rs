@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use crate::error::Error;
+use crate::focus::Focus;
+use crate::node::NodeValue;
+
+use super::cone::Cone;
+use super::domain::Domain;
+use super::log::NodeEvent;
+
+
+/// 自定义的冲突处理：参数为位置、后到的写入和已有的值，不存在的一边为None；
+/// 返回合并后的值，返回None时删除该位置。它在合并更新时调用，其中不能再读写domain
+pub type ConflictResolver = Arc<
+    dyn Fn(&Arc<Focus>, Option<&Arc<NodeValue>>, Option<&Arc<NodeValue>>) -> Option<Arc<NodeValue>>
+    + Send + Sync
+>;
+
+/// 从同一个旧版本出发的两个写入修改了同一位置时的处理方式
+#[derive(Clone, Default)]
+pub enum ConflictPolicy {
+    /// 保留txid较大、即后到的写入
+    #[default]
+    LastWriterWins,
+    /// 保留先到的写入
+    FirstWriterWins,
+    /// 后到的写入返回`Error::Conflict`，不做任何修改
+    Reject,
+    Resolver(ConflictResolver),
+}
+
+impl ConflictPolicy {
+    pub fn resolver<F>(func: F) -> Self
+    where
+        F: Fn(&Arc<Focus>, Option<&Arc<NodeValue>>, Option<&Arc<NodeValue>>) -> Option<Arc<NodeValue>>
+            + Send + Sync + 'static,
+    {
+        ConflictPolicy::Resolver(Arc::new(func))
+    }
+}
+
+impl ::std::fmt::Debug for ConflictPolicy {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        match self {
+            ConflictPolicy::LastWriterWins => fmt.write_str("LastWriterWins"),
+            ConflictPolicy::FirstWriterWins => fmt.write_str("FirstWriterWins"),
+            ConflictPolicy::Reject => fmt.write_str("Reject"),
+            ConflictPolicy::Resolver(_) => fmt.write_str("Resolver"),
+        }
+    }
+}
+
+fn same_node(a: Option<&Arc<NodeValue>>, b: Option<&Arc<NodeValue>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b) || a.deep_eq(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+impl Cone {
+    /// 合并更新线时按冲突策略决定结果，并记录冲突事件。
+    /// 先后按两边写入的txid判断，同一事务中的写入以合并的顺序为准
+    pub(crate) fn resolve_conflict(
+        &self,
+        policy: &ConflictPolicy,
+        focus: &Arc<Focus>,
+        (ours, ours_txid): (Option<&Arc<NodeValue>>, u64),
+        (theirs, theirs_txid): (Option<&Arc<NodeValue>>, u64),
+    ) -> Option<Arc<NodeValue>> {
+        let value = match policy {
+            ConflictPolicy::LastWriterWins if ours_txid >= theirs_txid => ours.cloned(),
+            ConflictPolicy::LastWriterWins => theirs.cloned(),
+            // 拒绝的策略在写入时已检查，这里保留先到的值
+            ConflictPolicy::FirstWriterWins | ConflictPolicy::Reject if ours_txid < theirs_txid => ours.cloned(),
+            ConflictPolicy::FirstWriterWins | ConflictPolicy::Reject => theirs.cloned(),
+            ConflictPolicy::Resolver(resolver) => resolver(focus, ours, theirs),
+        };
+
+        self.log_conflict(focus, ours, theirs, value.clone(), false);
+        value
+    }
+
+    /// 策略为Reject时，检查focus处的值是否已不是写入时所见的base
+    pub(crate) fn check_conflict(
+        &self,
+        focus: &Arc<Focus>,
+        base: Option<&Arc<NodeValue>>,
+        ours: Option<&Arc<NodeValue>>,
+    ) -> Result<(), Error> {
+        if !matches!(*self.conflict_policy.read().unwrap(), ConflictPolicy::Reject) {
+            return Ok(());
+        }
+
+        let current = self.get_focus_node(focus).ok().map(|(_, node)| node);
+        if same_node(base, current.as_ref()) {
+            return Ok(());
+        }
+
+        self.log_conflict(focus, ours, current.as_ref(), current.clone(), true);
+
+        let none = Arc::new(NodeValue::None);
+        Error::conflict(
+            focus,
+            ours.unwrap_or(&none).clone(),
+            current.unwrap_or(none),
+        )
+    }
+
+    fn log_conflict(
+        &self,
+        focus: &Arc<Focus>,
+        ours: Option<&Arc<NodeValue>>,
+        theirs: Option<&Arc<NodeValue>>,
+        value: Option<Arc<NodeValue>>,
+        rejected: bool,
+    ) {
+        let logger = &self.logger;
+
+        logger.push(NodeEvent::Conflict {
+            txid: logger.current_txid(),
+            focus: focus.clone(),
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+            value,
+            rejected,
+        });
+    }
+}
+
+impl Domain {
+    pub fn set_conflict_policy(&self, policy: ConflictPolicy) {
+        *self.cone.conflict_policy.write().unwrap() = policy;
+    }
+
+    pub fn conflict_policy(&self) -> ConflictPolicy {
+        self.cone.conflict_policy.read().unwrap().clone()
+    }
+}
@@ -2,12 +2,13 @@
 use std::sync::Arc;
 use crate::spot::Spot;
 use crate::error::Error;
+use crate::diff::DiffOp;
 
 use super::cone::Cone;
 use super::log::ChangeLogger;
 
 pub struct Domain {
-    cone: Arc<Cone>
+    pub(crate) cone: Arc<Cone>
 }
 
 impl Domain {
@@ -17,13 +18,20 @@ impl Domain {
         }
     }
 
+    /// 由JSON文本创建domain，整个文档作为根节点挂载
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let domain = Domain::new();
+        domain.root().set_json(json)?;
+        Ok(domain)
+    }
+
     pub fn root(&self) -> Spot {
-        Spot {
-            cone: self.cone.clone(),
-            focus: self.cone.root_focus.clone(),
-            node: self.cone.get_root_node(),
-            parent: None,
-        }
+        Spot::new(
+            self.cone.clone(),
+            self.cone.root_focus.clone(),
+            self.cone.get_root_node(),
+            None,
+        )
     }
 
     #[inline]
@@ -31,6 +39,11 @@ impl Domain {
         self.root().navigate(path)
     }
 
+    /// 从本domain的根变化到other的根所需的操作
+    pub fn diff(&self, other: &Domain) -> Vec<DiffOp> {
+        self.root().diff(&other.root())
+    }
+
     pub fn log(&self) -> &ChangeLogger {
         &self.cone.logger
     }
@@ -12,16 +12,21 @@ impl Cone {
         old_value: Arc<NodeValue>,
         new_value: Arc<NodeValue>,
     ) {
+        let _writer = self.lock_writer();
         let logger = &self.logger;
 
         let txid = logger.new_txid();
 
-        logger.push(NodeEvent::RootUpdated {
+        let event = NodeEvent::RootUpdated {
             txid: txid,
-            focus: focus,
+            focus: focus.clone(),
             value: new_value.clone(),
-        });
+        };
+        self.notifier.stage(&event, Some(old_value.clone()));
+        logger.push(event);
 
+        self.stamps.written(&focus, txid);
+        self.node_cache.mark_dirty(&focus);
         self.push_change(old_value, new_value);
     }
 
@@ -32,15 +37,18 @@ impl Cone {
         new_value: &Arc<NodeValue>,
         new_parent: &Arc<NodeValue>,
     ) {
+        let _writer = self.lock_writer();
         let logger = &self.logger;
 
         let txid = logger.new_txid();
 
-        logger.push(NodeEvent::ValueCreated {
+        let event = NodeEvent::ValueCreated {
             txid: txid,
             focus: focus.clone(),
             value: new_value.clone(),
-        });
+        };
+        self.notifier.stage(&event, None);
+        logger.push(event.clone());
 
         self.push_parent_node(new_value.clone(), new_parent.clone());
 
@@ -48,7 +56,11 @@ impl Cone {
             focus.get_parent().unwrap().clone(),
             old_parent.clone(),
             new_parent.clone(),
+            event,
         );
+
+        self.stamps.written(focus, txid);
+        self.node_cache.mark_dirty(focus);
     }
 
     pub(crate) fn log_value_updated(
@@ -59,15 +71,18 @@ impl Cone {
         new_value: Arc<NodeValue>,
         new_parent: Arc<NodeValue>,
     ) {
+        let _writer = self.lock_writer();
         let logger = &self.logger;
 
         let txid = logger.new_txid();
 
-        logger.push(NodeEvent::ValueUpdated {
+        let event = NodeEvent::ValueUpdated {
             txid: txid,
             focus: focus.clone(),
             value: new_value.clone(),
-        });
+        };
+        self.notifier.stage(&event, Some(old_value.clone()));
+        logger.push(event.clone());
 
         self.push_parent_node(new_value.clone(), new_parent.clone());
         self.push_change(old_value, new_value);
@@ -76,7 +91,11 @@ impl Cone {
             focus.get_parent().unwrap().clone(),
             old_parent.clone(),
             new_parent.clone(),
+            event,
         );
+
+        self.stamps.written(&focus, txid);
+        self.node_cache.mark_dirty(&focus);
     }
 
     pub(crate) fn log_value_deleted(
@@ -86,21 +105,28 @@ impl Cone {
         old_value: &Arc<NodeValue>,
         new_parent: &Arc<NodeValue>,
     ) {
+        let _writer = self.lock_writer();
         let logger = &self.logger;
 
         let txid = logger.new_txid();
 
-        logger.push(NodeEvent::ValueUpdated {
+        let event = NodeEvent::ValueDeleted {
             txid: txid,
             focus: focus.clone(),
             value: old_value.clone(),
-        });
+        };
+        self.notifier.stage(&event, Some(old_value.clone()));
+        logger.push(event.clone());
 
         self.pending_inode_update(
             focus.get_parent().unwrap().clone(),
             old_parent.clone(),
             new_parent.clone(),
+            event,
         );
+
+        self.stamps.deleted(focus, txid);
+        self.node_cache.mark_dirty(focus);
     }
 
     pub(crate) fn log_listitem_inserted(
@@ -110,15 +136,18 @@ impl Cone {
         new_value: &Arc<NodeValue>,
         new_parent: &Arc<NodeValue>,
     ) {
+        let _writer = self.lock_writer();
         let logger = &self.logger;
 
         let txid = logger.new_txid();
 
-        logger.push(NodeEvent::ListItemInserted {
+        let event = NodeEvent::ListItemInserted {
             txid: txid,
             focus: focus.clone(),
             value: new_value.clone(),
-        });
+        };
+        self.notifier.stage(&event, None);
+        logger.push(event.clone());
 
         self.push_parent_node(new_value.clone(), new_parent.clone());
 
@@ -126,7 +155,12 @@ impl Cone {
             focus.get_parent().unwrap().clone(),
             old_parent.clone(),
             new_parent.clone(),
+            event,
         );
+
+        // 之后的列表项下标随之改变
+        self.stamps.written(focus.get_parent().unwrap(), txid);
+        self.node_cache.mark_dirty(focus.get_parent().unwrap());
     }
 
     pub(crate) fn log_listitem_deleted(
@@ -136,21 +170,29 @@ impl Cone {
         old_value: &Arc<NodeValue>,
         new_parent: &Arc<NodeValue>,
     ) {
+        let _writer = self.lock_writer();
         let logger = &self.logger;
 
         let txid = logger.new_txid();
 
-        logger.push(NodeEvent::ListItemDeleted {
+        let event = NodeEvent::ListItemDeleted {
             txid: txid,
             focus: focus.clone(),
             value: old_value.clone(),
-        });
+        };
+        self.notifier.stage(&event, Some(old_value.clone()));
+        logger.push(event.clone());
 
         self.pending_inode_update(
             focus.get_parent().unwrap().clone(),
             old_parent.clone(),
             new_parent.clone(),
+            event,
         );
+
+        // 之后的列表项下标随之改变
+        self.stamps.written(focus.get_parent().unwrap(), txid);
+        self.node_cache.mark_dirty(focus.get_parent().unwrap());
     }
 
     pub(crate) fn log_internal_root_updated(
@@ -161,7 +203,7 @@ impl Cone {
     ) {
         let logger = &self.logger;
 
-        let txid = logger.new_txid();
+        let txid = logger.current_txid();
 
         logger.push(NodeEvent::InternalRootUpdated {
             txid: txid,
@@ -180,7 +222,7 @@ impl Cone {
     ) {
         let logger = &self.logger;
 
-        let txid = logger.new_txid();
+        let txid = logger.current_txid();
 
         logger.push(NodeEvent::InternalLineUpdated {
             txid: txid,
@@ -198,11 +240,13 @@ impl Cone {
         focus: Arc<Focus>,
         old_node: Arc<NodeValue>,
         new_node: Arc<NodeValue>,
+        event: NodeEvent,
     ) {
         let pending_update = PendingUpdate {
                 focus: focus.clone(),
                 old_node: old_node,
                 new_node: new_node,
+                event,
         };
 
         let mut pending = self.logger.pending.write().unwrap();
@@ -226,12 +270,4 @@ impl Cone {
         let mut parents = self.logger.parents.write().unwrap();
         parents.insert(node, parent);
     }
-
-    #[inline]
-    pub(super) fn get_parent_node(&self, node: &Arc<NodeValue>) -> Option<Arc<NodeValue>> {
-        let node_parents = self.logger.parents.read().unwrap();
-
-        let parent_node = node_parents.get(node)?;
-        Some(parent_node.clone())
-    }
 }
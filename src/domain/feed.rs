@@ -0,0 +1,134 @@
+use std::sync::atomic::Ordering;
+
+use crate::error::Error;
+
+use super::domain::Domain;
+use super::log::{ChangeLogger, NodeEvent};
+use super::writer::thread_no;
+
+
+impl ChangeLogger {
+    /// txid之后已提交的变更，按提交顺序排列。内部节点的更新不在其中。
+    /// 尚未合并的写入在合并时可能被修正或丢弃，因此也不在其中；
+    /// 进行中的事务的变更只有事务所在的线程可以读取
+    pub fn changes_since(&self, txid: u64) -> Result<Vec<NodeEvent>, Error> {
+        let compacted = *self.compacted.read().unwrap();
+        if txid < compacted {
+            return Error::compacted(txid, compacted);
+        }
+
+        let committed = *self.committed.read().unwrap();
+        let own_txid = match *self.transaction.read().unwrap() {
+            Some(open_txid) if self.transaction_thread.load(Ordering::SeqCst) == thread_no() => Some(open_txid),
+            _ => None,
+        };
+
+        let log = self.log.read().unwrap();
+        let changes = log.iter()
+            .filter(|event| !event.is_internal())
+            .filter(|event| event.txid() > txid)
+            .filter(|event| event.txid() <= committed || Some(event.txid()) == own_txid)
+            .cloned()
+            .collect();
+
+        Ok(changes)
+    }
+
+    /// 最早仍可以读取的位置，更早的变更已被移除
+    pub fn compacted_txid(&self) -> u64 {
+        *self.compacted.read().unwrap()
+    }
+
+    /// 创建或重置名为name的游标，之后从txid之后开始读取。
+    /// 消费者重启时用自己保存的位置恢复游标
+    pub fn open_cursor(&self, name: &str, txid: u64) -> Result<(), Error> {
+        let compacted = *self.compacted.read().unwrap();
+        if txid < compacted {
+            return Error::compacted(txid, compacted);
+        }
+
+        self.cursors.write().unwrap().insert(name.to_string(), txid);
+        Ok(())
+    }
+
+    pub fn close_cursor(&self, name: &str) -> Result<u64, Error> {
+        match self.cursors.write().unwrap().remove(name) {
+            Some(txid) => Ok(txid),
+            None => Error::no_such_cursor(name),
+        }
+    }
+
+    /// 游标已处理到的txid，可由消费者保存下来
+    pub fn cursor_position(&self, name: &str) -> Result<u64, Error> {
+        match self.cursors.read().unwrap().get(name) {
+            Some(txid) => Ok(*txid),
+            None => Error::no_such_cursor(name),
+        }
+    }
+
+    /// 全部游标及其位置。游标只保存在内存中，随domain一起释放；
+    /// 需要保留时由调用者保存这份列表，之后用load_cursors恢复
+    pub fn cursors(&self) -> Vec<(String, u64)> {
+        let mut cursors = self.cursors.read().unwrap().iter()
+            .map(|(name, txid)| (name.clone(), *txid))
+            .collect::<Vec<_>>();
+        cursors.sort();
+        cursors
+    }
+
+    /// 按cursors导出的列表创建或重置游标。
+    /// 有位置已被移除时返回Compacted，不恢复其中任何一个游标
+    pub fn load_cursors(&self, cursors: &[(String, u64)]) -> Result<(), Error> {
+        let compacted = *self.compacted.read().unwrap();
+        if let Some((_, txid)) = cursors.iter().find(|(_, txid)| *txid < compacted) {
+            return Error::compacted(*txid, compacted);
+        }
+
+        let mut current = self.cursors.write().unwrap();
+        for (name, txid) in cursors {
+            current.insert(name.clone(), *txid);
+        }
+        Ok(())
+    }
+
+    /// 游标位置之后的变更，读取不移动游标
+    pub fn read_cursor(&self, name: &str) -> Result<Vec<NodeEvent>, Error> {
+        let txid = self.cursor_position(name)?;
+        self.changes_since(txid)
+    }
+
+    /// 处理完成后将游标移到txid
+    pub fn advance_cursor(&self, name: &str, txid: u64) -> Result<(), Error> {
+        match self.cursors.write().unwrap().get_mut(name) {
+            Some(position) => {
+                *position = txid.max(*position);
+                Ok(())
+            },
+            None => Error::no_such_cursor(name),
+        }
+    }
+}
+
+impl Domain {
+    /// 从日志中移除txid及之前的变更，之后无法再从这些位置读取。
+    /// 等待其他线程的事务结束后进行，在事务之中调用时返回InTransaction
+    pub fn compact_log(&self, txid: u64) -> Result<(), Error> {
+        let cone = &self.cone;
+        let _writer = cone.lock_writer();
+
+        let logger = &cone.logger;
+        if logger.transaction.read().unwrap().is_some() {
+            return Error::in_transaction();
+        }
+
+        let txid = txid.min(*logger.committed.read().unwrap());
+        let mut compacted = logger.compacted.write().unwrap();
+        if txid <= *compacted {
+            return Ok(());
+        }
+        *compacted = txid;
+
+        logger.log.write().unwrap().retain(|event| event.txid() > txid);
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
-use super::cone::Cone;
+use super::cone::{Cone, get_item_node};
 use super::log::{NodeEvent, PendingUpdate};
+use crate::error::Error;
 use crate::focus::{AccessKey, Focus, FocusLocator};
 use crate::node::NodeValue;
+use crate::merge::{merge_three_way, Resolve};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 impl Cone {
@@ -10,42 +13,41 @@ impl Cone {
         focus: Arc<Focus>,
         old_node: Arc<NodeValue>,
         new_node: Arc<NodeValue>,
+        event: NodeEvent,
     ) {
         // self.push_pending(focus, old_node, new_node);
-        self.push_pending(focus.clone(), old_node.clone(), new_node.clone());
+        self.push_pending(focus.clone(), old_node.clone(), new_node.clone(), event);
 
         // self.update_internal_node(focus, old_node, new_node);
     }
 
     pub fn solve_pending_at(&self, focus: &Arc<Focus>) {
+        // 只由持有写入锁的线程合并，其他线程读取已提交的根节点
+        if !self.is_writer() {
+            return;
+        }
+
         let mut pending = self.logger.pending.write().unwrap();
         if pending.len() == 0 {
             return;
         }
 
-        // 将focus对应的节点，连续变更合并一条“线”，由于并发，可能存在多条线，然后合并
-
-        let sorted_focueses = {
-            let mut pending_focuses = pending.keys().cloned().collect::<Vec<Arc<Focus>>>();
-            pending_focuses.sort_by(|a, b| a.cmp(b).reverse()); // 从深到浅排序
-            pending_focuses
-            // 取得一个最深的节点
-            // pending_focuses.pop().unwrap().clone()
-        };
-
-        for deepest_focus in sorted_focueses {
-            let updating_lines = build_update_lines(&pending.get(&deepest_focus).unwrap());
+        // 将focus对应的节点，连续变更合并一条“线”，由于并发，可能存在多条线。
+        // 每条线以其起点为共同祖先，与该节点当前的版本三方合并，
+        // 从不同Spot对不同位置的修改因此都能保留。
+        // 每次取出最深的节点处理，向上更新时会在父节点上追加新的pending，直到根节点
+        let policy = self.conflict_policy.read().unwrap().clone();
 
-            assert!(updating_lines.len() <= 1);
+        while let Some(deepest_focus) = pending.keys().max().cloned() {
+            let updates = pending.remove(&deepest_focus).unwrap();
+            let updating_lines = build_update_lines(&updates);
 
-            for line in updating_lines {
-                println!("Path: {:?}", deepest_focus.access_path());
-                for u in &line {
-                    println!("11111 {:p} => {:p}", u.old_node, u.new_node);
-                }
+            let current_node = self.peek_focus_node(&pending, &deepest_focus)
+                .unwrap_or_else(|| updating_lines[0][0].old_node.clone());
 
+            for line in &updating_lines {
                 if line.len() >= 2 {
-                    for upd in &line {
+                    for upd in line {
                         self.log_internal_line_updated(
                             upd.focus.clone(),
                             upd.old_node.clone(),
@@ -53,76 +55,257 @@ impl Cone {
                         );
                     }
                 }
+            }
 
-                if let Some(_) = (&deepest_focus).get_parent() {
-                    let upd = self.update_internal_node(
-                        deepest_focus.clone(),
-                        line.first().unwrap().old_node.clone(),
-                        line.last().unwrap().new_node.clone(),
-                    );
+            // 写入与当前的节点合并，current_txid是已合并的写入中最大的txid，
+            // 当前节点上合并之前就有的值更早
+            let mut current_txid = 0;
+            let mut new_node = current_node.clone();
+
+            if is_list(&current_node) && updates.iter().all(|upd| is_list(&upd.old_node)) {
+                // 列表按写入顺序逐个重放插入、删除和修改，而不是按下标比较整条线
+                for upd in &updates {
+                    let txid = upd.event.txid();
+                    let mut resolve = |focus: &Arc<Focus>, current: Option<&Arc<NodeValue>>, incoming: Option<&Arc<NodeValue>>| {
+                        self.resolve_conflict(&policy, focus, (incoming, txid), (current, current_txid))
+                    };
+                    new_node = match self.replay_list_update(&deepest_focus, &new_node, upd, &mut resolve) {
+                        Ok(replayed) => replayed,
+                        // 写入时所见的项无法定位，退回按下标三方合并
+                        Err(_) => or_none(merge_three_way(&deepest_focus, &upd.old_node, &new_node, &upd.new_node, &mut resolve)),
+                    };
+                    current_txid = current_txid.max(txid);
+                }
+            } else {
+                // 按各条线最后一次写入的txid排序，后写入的线后合并
+                let mut updating_lines = updating_lines;
+                updating_lines.sort_by_key(|line| line.last().unwrap().event.txid());
+
+                for line in &updating_lines {
+                    let txid = line.last().unwrap().event.txid();
+                    let mut resolve = |focus: &Arc<Focus>, current: Option<&Arc<NodeValue>>, incoming: Option<&Arc<NodeValue>>| {
+                        self.resolve_conflict(&policy, focus, (incoming, txid), (current, current_txid))
+                    };
+
+                    let base_node = &line.first().unwrap().old_node;
+                    let line_node = &line.last().unwrap().new_node;
+                    new_node = or_none(merge_three_way(&deepest_focus, base_node, &new_node, line_node, &mut resolve));
+                    current_txid = current_txid.max(txid);
+                }
+            }
 
-                    pending.get_mut(&upd.focus).unwrap().push(upd);
-                } else {
-                    let old_node = line.first().unwrap().old_node.clone();
-                    let new_node = line.last().unwrap().new_node.clone();
+            if let Some(_) = (&deepest_focus).get_parent() {
+                let upd = self.update_internal_node(
+                    &pending,
+                    deepest_focus.clone(),
+                    new_node,
+                );
 
-                    self.log_internal_root_updated(deepest_focus.clone(), old_node, new_node.clone());
-                    self.remount_root(new_node);
+                if let Some(upd) = upd {
+                    pending.entry(upd.focus.clone()).or_default().push(upd);
                 }
+            } else {
+                self.log_internal_root_updated(deepest_focus.clone(), current_node, new_node.clone());
+                self.remount_root(new_node);
             }
-            pending.remove(&deepest_focus);
         }
+    }
+
+    /// 将列表上的一次写入重放到当前的列表上。插入、删除和修改按写入时所见的
+    /// 列表项定位，而不是按下标，其他写入在之前插入或删除的项因此不受影响。
+    /// 实际位置与记录时不同时修正日志中的事件
+    fn replay_list_update(
+        &self,
+        focus: &Arc<Focus>,
+        current: &Arc<NodeValue>,
+        upd: &PendingUpdate,
+        resolve: &mut Resolve<'_>,
+    ) -> Result<Arc<NodeValue>, Error> {
+        // 写入之后没有其他写入
+        if Arc::ptr_eq(&upd.old_node, current) {
+            return Ok(upd.new_node.clone());
+        }
+
+        let (old_list, current_list) = match (upd.old_node.as_ref(), current.as_ref()) {
+            (NodeValue::List(old_list), NodeValue::List(current_list)) => (old_list, current_list),
+            _ => unreachable!("both nodes should be lists"),
+        };
+        let index = match upd.event.focus().get_access_key() {
+            AccessKey::Index(index) if upd.event.focus().get_parent() == Some(focus) => index,
+            _ => return Ok(or_none(merge_three_way(focus, &upd.old_node, current, &upd.new_node, resolve))),
+        };
+        // 同一个节点可能在列表中出现多次，按它是第几次出现来定位
+        let old_item = old_list.occurrence(index);
+
+        match &upd.event {
+            NodeEvent::ListItemInserted { value, .. } => {
+                // 插入到写入时其后的那一项之前，写入时在末尾则仍追加到末尾
+                let position = match old_item {
+                    Some((next, nth)) => current_list.position(next, nth)
+                        .unwrap_or_else(|| index.min(current_list.len())),
+                    None => current_list.len(),
+                };
+
+                self.amend_event(&upd.event, Some(upd.event.with_focus(focus.focus(position))));
+                Ok(Arc::new(NodeValue::List(current_list.insert(position, value.clone()))))
+            },
+            NodeEvent::ListItemDeleted { .. } => {
+                let (value, nth) = match old_item {
+                    Some(item) => item,
+                    None => return Error::no_such_item(focus, &AccessKey::Index(index)),
+                };
+                match current_list.position(value, nth) {
+                    Some(position) => {
+                        self.amend_event(&upd.event, Some(upd.event.with_focus(focus.focus(position))));
+                        Ok(Arc::new(NodeValue::List(current_list.remove(position))))
+                    },
+                    // 已被其他写入删除
+                    None => {
+                        self.amend_event(&upd.event, None);
+                        Ok(current.clone())
+                    },
+                }
+            },
+            NodeEvent::ValueUpdated { value, .. } | NodeEvent::InternalNodeUpdated { value, .. } => {
+                let (old_item, nth) = match old_item {
+                    Some(item) => item,
+                    None => return Error::no_such_item(focus, &AccessKey::Index(index)),
+                };
+
+                if let Some(position) = current_list.position(old_item, nth) {
+                    self.amend_event(&upd.event, Some(upd.event.with_focus(focus.focus(position))));
+                    return Ok(Arc::new(NodeValue::List(current_list.set_item(position, value.clone()).0)));
+                }
 
-        // println!("ZZZ\n{:?}", lines);
+                // 该项已被其他写入原地修改，按下标三方合并
+                let replaced = current_list.get_item(index)
+                    .filter(|item| old_list.position(item, 0).is_none());
+                if let Some(current_item) = replaced {
+                    let merged_list = match merge_three_way(&focus.focus(index), old_item, current_item, value, resolve) {
+                        Some(merged) => current_list.set_item(index, merged).0,
+                        None => current_list.remove(index),
+                    };
+                    return Ok(Arc::new(NodeValue::List(merged_list)));
+                }
+
+                // 该项已被其他写入删除，按冲突策略决定是否重新加入
+                let position = index.min(current_list.len());
+                let item_focus = focus.focus(position);
+                match resolve(&item_focus, None, Some(value)) {
+                    Some(item) => {
+                        self.amend_event(&upd.event, Some(NodeEvent::ListItemInserted {
+                            txid: upd.event.txid(),
+                            focus: item_focus,
+                            value: item.clone(),
+                        }));
+                        Ok(Arc::new(NodeValue::List(current_list.insert(position, item))))
+                    },
+                    // 这次写入不再生效，冲突随之记为被拒绝
+                    None => {
+                        self.amend_event(&upd.event, None);
+                        let conflict = |rejected| NodeEvent::Conflict {
+                            txid: self.logger.current_txid(),
+                            focus: item_focus.clone(),
+                            ours: Some(value.clone()),
+                            theirs: None,
+                            value: None,
+                            rejected,
+                        };
+                        self.amend_event(&conflict(false), Some(conflict(true)));
+                        Ok(current.clone())
+                    },
+                }
+            },
+            _ => Ok(or_none(merge_three_way(focus, &upd.old_node, current, &upd.new_node, resolve))),
+        }
     }
 
+    /// 日志和暂存的通知中的event换成amended，None时移除
+    fn amend_event(&self, event: &NodeEvent, amended: Option<NodeEvent>) {
+        if amended.as_ref() == Some(event) {
+            return;
+        }
+
+        let mut log = self.logger.log.write().unwrap();
+        if let Some(index) = log.iter().rposition(|logged| logged == event) {
+            match &amended {
+                Some(amended) => log[index] = amended.clone(),
+                None => {
+                    log.remove(index);
+                },
+            }
+        }
+
+        self.notifier.amend_staged(event, amended.as_ref());
+    }
+
+    /// 将focus的新节点写入其父节点的最新版本，返回父节点上的更新。
+    /// 父节点已被移除或替换为其他类型时，该更新已被覆盖，返回None
+    #[allow(clippy::mutable_key_type)] // Focus按指针比较和哈希
     pub fn update_internal_node(
         &self,
+        pending: &HashMap<Arc<Focus>, Vec<PendingUpdate>>,
         focus: Arc<Focus>,
-        old_node: Arc<NodeValue>,
         new_node: Arc<NodeValue>,
-    ) -> PendingUpdate {
-        // 取得旧节点的父节点，向上更新
-        if let Some(old_parent) = self.get_parent_node(&old_node) {
-            // println!("222 {:?} {:?}", old_parent, focus);
-            let (new_parent, _old_item) = match (old_parent.as_ref(), focus.get_access_key()) {
-                (NodeValue::Map(map_value), AccessKey::Key(ref key)) => {
-                    let (new_map, old_item) = map_value.set_item(key.to_string(), new_node.clone());
-                    let new_parent_node = Arc::new(NodeValue::Map(new_map));
-                    (new_parent_node, old_item)
-                }
-                (NodeValue::List(list_value), AccessKey::Index(index)) => {
-                    let (new_list, old_item) = list_value.set_item(index, new_node.clone());
-                    let new_parent_node = Arc::new(NodeValue::List(new_list));
-                    (new_parent_node, old_item)
-                }
-                (_, _) => {
-                    panic!(
-                        "mismatch map/list with access_key while accessing internal node at '{}'",
-                        focus.access_path()
-                    );
-                }
-            };
-
-            self.log_inode_updated(
-                focus.clone(),
-                old_parent.clone(),
-                old_node,
-                new_node,
-                new_parent.clone(),
-            );
-
-            PendingUpdate {
-                focus: focus.get_parent().unwrap().clone(),
-                old_node: old_parent,
-                new_node: new_parent,
+    ) -> Option<PendingUpdate> {
+        let parent_focus = focus.get_parent()?;
+
+        // 以父节点的最新版本为准向上更新，而不是写入时所见的旧版本
+        let old_parent = self.peek_focus_node(pending, parent_focus)?;
+        let access_key = focus.get_access_key();
+        let old_node = get_item_node(parent_focus, &old_parent, &access_key).ok()?;
+
+        let new_parent = match (old_parent.as_ref(), access_key) {
+            (NodeValue::Map(map_value), AccessKey::Key(key)) => {
+                let (new_map, _) = map_value.set_item(key, new_node.clone());
+                Arc::new(NodeValue::Map(new_map))
             }
-        } else {
-            panic!("")
-            // update root internally
+            (NodeValue::List(list_value), AccessKey::Index(index)) => {
+                let (new_list, _) = list_value.set_item(index, new_node.clone());
+                Arc::new(NodeValue::List(new_list))
+            }
+            (_, _) => {
+                panic!(
+                    "mismatch map/list with access_key while accessing internal node at '{}'",
+                    focus.access_path()
+                );
+            }
+        };
 
-            // self.log_internal_root_updated(focus, old_node.clone(), new_node.clone());
-            // self.remount_root(new_node);
+        let event = self.log_inode_updated(
+            focus.clone(),
+            old_parent.clone(),
+            old_node,
+            new_node,
+            new_parent.clone(),
+        );
+        self.stamps.propagate(&focus);
+
+        Some(PendingUpdate {
+            focus: parent_focus.clone(),
+            old_node: old_parent,
+            new_node: new_parent,
+            event,
+        })
+    }
+
+    /// 取得focus处节点的最新版本：pending中尚未合并的更新优先，否则从根节点逐层查找
+    #[allow(clippy::mutable_key_type)]
+    fn peek_focus_node(
+        &self,
+        pending: &HashMap<Arc<Focus>, Vec<PendingUpdate>>,
+        focus: &Arc<Focus>,
+    ) -> Option<Arc<NodeValue>> {
+        if let Some(upd) = pending.get(focus).and_then(|updates| updates.last()) {
+            return Some(upd.new_node.clone());
+        }
+
+        match focus.get_parent() {
+            Some(parent_focus) => {
+                let parent_node = self.peek_focus_node(pending, parent_focus)?;
+                get_item_node(parent_focus, &parent_node, &focus.get_access_key()).ok()
+            }
+            None => Some(self.root_node.load_full()),
         }
     }
 
@@ -133,16 +316,17 @@ impl Cone {
         old_value: Arc<NodeValue>,
         new_value: Arc<NodeValue>,
         new_parent: Arc<NodeValue>,
-    ) {
+    ) -> NodeEvent {
         let logger = &self.logger;
 
-        let txid = logger.new_txid();
+        let txid = logger.current_txid();
 
-        logger.push(NodeEvent::InternalNodeUpdated {
+        let event = NodeEvent::InternalNodeUpdated {
             txid: txid,
             focus: focus.clone(),
             value: new_value.clone(),
-        });
+        };
+        logger.push(event.clone());
 
         self.push_parent_node(new_value.clone(), new_parent.clone());
         self.push_change(old_value, new_value.clone());
@@ -153,6 +337,8 @@ impl Cone {
         //     // the root node
         //     panic!("")
         // }
+
+        event
     }
 
     // pub fn update_internal_node(
@@ -228,6 +414,17 @@ impl Cone {
     // }
 }
 
+/// 合并的结果为删除时，该位置自身无法在这里移除，记为None
+#[inline]
+fn or_none(node: Option<Arc<NodeValue>>) -> Arc<NodeValue> {
+    node.unwrap_or_else(|| Arc::new(NodeValue::None))
+}
+
+#[inline]
+fn is_list(node: &Arc<NodeValue>) -> bool {
+    matches!(node.as_ref(), NodeValue::List(_))
+}
+
 fn build_update_lines(updates: &Vec<PendingUpdate>) -> Vec<Vec<PendingUpdate>> {
     let mut lines: Vec<Vec<PendingUpdate>> = Vec::new();
     let mut prev_new_nodes: Vec<&Arc<NodeValue>> = Vec::new();
@@ -247,17 +444,18 @@ fn build_update_lines(updates: &Vec<PendingUpdate>) -> Vec<Vec<PendingUpdate>> {
     // 按顺序检查updates，如果前后new_node和old_node相等连续，合并成一线，
     // 否则另开一条线
     for upd in update_iter {
-        for line_idx in 0..lines.len() {
-            if *prev_new_nodes[line_idx] == upd.old_node {
-                lines[line_idx].push((*upd).clone());
-            } else {
-                // a new updating line
-                let mut line: Vec<PendingUpdate> = Vec::new();
-                line.push((*upd).clone());
+        let line_idx = prev_new_nodes.iter().position(|prev| **prev == upd.old_node);
 
-                lines.push(line);
-                prev_new_nodes.push(&upd.new_node);
-            }
+        if let Some(line_idx) = line_idx {
+            lines[line_idx].push((*upd).clone());
+            prev_new_nodes[line_idx] = &upd.new_node;
+        } else {
+            // a new updating line
+            let mut line: Vec<PendingUpdate> = Vec::new();
+            line.push((*upd).clone());
+
+            lines.push(line);
+            prev_new_nodes.push(&upd.new_node);
         }
     }
 
@@ -1,11 +1,15 @@
 use std::sync::{Arc, RwLock};
+use std::sync::atomic::AtomicU64;
 
 use crate::focus::{Focus, FocusLocator};
 
-use crate::node::NodeValue;
+use crate::node::{NodeValue, ListValue};
+use crate::error::Error;
+use crate::patch::patch_operation;
 use std::collections::HashMap;
+use std::ops::RangeBounds;
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub enum NodeEvent {
     RootUpdated {
         txid: u64,
@@ -53,13 +57,89 @@ pub enum NodeEvent {
         focus: Arc<Focus>,
         value: Arc<NodeValue>,
     },
+    /// 两个写入修改了同一位置：ours是后到的写入，theirs是已有的值，
+    /// value是按冲突策略得出的结果，None表示不存在。
+    /// rejected表示后到的写入没有生效，日志中也没有它的修改
+    Conflict {
+        txid: u64,
+        focus: Arc<Focus>,
+        ours: Option<Arc<NodeValue>>,
+        theirs: Option<Arc<NodeValue>>,
+        value: Option<Arc<NodeValue>>,
+        rejected: bool,
+    },
 }
 
-#[derive(Debug, Clone)]
+impl NodeEvent {
+    pub fn txid(&self) -> u64 {
+        use NodeEvent::*;
+
+        match self {
+            RootUpdated { txid, .. } => *txid,
+            ValueCreated { txid, .. } => *txid,
+            ValueUpdated { txid, .. } => *txid,
+            ValueDeleted { txid, .. } => *txid,
+            ListItemInserted { txid, .. } => *txid,
+            ListItemDeleted { txid, .. } => *txid,
+            InternalNodeUpdated { txid, .. } => *txid,
+            InternalLineUpdated { txid, .. } => *txid,
+            InternalRootUpdated { txid, .. } => *txid,
+            Conflict { txid, .. } => *txid,
+        }
+    }
+
+    pub fn focus(&self) -> &Arc<Focus> {
+        use NodeEvent::*;
+
+        match self {
+            RootUpdated { focus, .. } => focus,
+            ValueCreated { focus, .. } => focus,
+            ValueUpdated { focus, .. } => focus,
+            ValueDeleted { focus, .. } => focus,
+            ListItemInserted { focus, .. } => focus,
+            ListItemDeleted { focus, .. } => focus,
+            InternalNodeUpdated { focus, .. } => focus,
+            InternalLineUpdated { focus, .. } => focus,
+            InternalRootUpdated { focus, .. } => focus,
+            Conflict { focus, .. } => focus,
+        }
+    }
+
+    /// 同一个修改发生在另一个位置
+    pub(crate) fn with_focus(&self, new_focus: Arc<Focus>) -> NodeEvent {
+        use NodeEvent::*;
+
+        let mut event = self.clone();
+        match &mut event {
+            RootUpdated { focus, .. }
+            | ValueCreated { focus, .. }
+            | ValueUpdated { focus, .. }
+            | ValueDeleted { focus, .. }
+            | ListItemInserted { focus, .. }
+            | ListItemDeleted { focus, .. }
+            | InternalNodeUpdated { focus, .. }
+            | InternalLineUpdated { focus, .. }
+            | InternalRootUpdated { focus, .. }
+            | Conflict { focus, .. } => *focus = new_focus,
+        }
+        event
+    }
+
+    /// 由叶子的变更推导出的内部节点更新
+    pub fn is_internal(&self) -> bool {
+        use NodeEvent::*;
+
+        matches!(self, 
+            InternalNodeUpdated { .. } | InternalLineUpdated { .. } | InternalRootUpdated { .. })
+    }
+}
+
+#[derive(Clone)]
 pub struct PendingUpdate {
     pub focus: Arc<Focus>,
     pub old_node: Arc<NodeValue>,
     pub new_node: Arc<NodeValue>,
+    pub event: NodeEvent, // 产生该更新的写入，合并列表时按它重放
 }
 
 pub struct ChangeLogger {
@@ -70,6 +150,11 @@ pub struct ChangeLogger {
     pub txid_max: RwLock<u64>,
     pub log: RwLock<Vec<NodeEvent>>,
     // pub pending: RwLock<Vec<PendingUpdate>>,
+    pub transaction: RwLock<Option<u64>>, // 正在进行的事务的txid
+    pub(crate) transaction_thread: AtomicU64, // 正在进行的事务所在线程的编号，0表示没有
+    pub compacted: RwLock<u64>, // 不大于该txid的变更已从日志中移除
+    pub committed: RwLock<u64>, // 不大于该txid的变更已合并并提交，不会再被修改
+    pub cursors: RwLock<HashMap<String, u64>>, // 各消费者已处理到的txid
 }
 
 impl ChangeLogger {
@@ -82,15 +167,39 @@ impl ChangeLogger {
             parents: RwLock::new(HashMap::new()),
             pending: RwLock::new(HashMap::new()),
             // pending: RwLock::new(Vec::new()),
+            transaction: RwLock::new(None),
+            transaction_thread: AtomicU64::new(0),
+            compacted: RwLock::new(0),
+            committed: RwLock::new(0),
+            cursors: RwLock::new(HashMap::new()),
         }
     }
 
+    /// 分配新的txid，事务中的变更共用事务的txid
     pub fn new_txid(&self) -> u64 {
+        if let Some(txid) = *self.transaction.read().unwrap() {
+            return txid;
+        }
+
         let mut txid_max = self.txid_max.write().unwrap();
         *txid_max += 1;
         *txid_max
     }
 
+    /// 待处理的更新已全部合并，到目前为止的变更不会再被修改
+    pub(crate) fn mark_committed(&self) {
+        *self.committed.write().unwrap() = *self.txid_max.read().unwrap();
+    }
+
+    /// 最近分配的txid，由已有变更推导出的内部事件沿用它
+    pub fn current_txid(&self) -> u64 {
+        if let Some(txid) = *self.transaction.read().unwrap() {
+            return txid;
+        }
+
+        *self.txid_max.read().unwrap()
+    }
+
     pub fn push(&self, event: NodeEvent) {
         let mut log = self.log.write().unwrap();
         log.push(event);
@@ -105,6 +214,25 @@ impl ChangeLogger {
         }
     }
 
+    /// 将txid在范围内的变更导出为RFC 6902 JSON Patch文档，路径相对于根节点。
+    /// 内部节点的更新由叶子的变更推导而来，不再重复导出
+    pub fn to_json_patch<R: RangeBounds<u64>>(&self, txids: R) -> Result<String, Error> {
+        let mut ops = ListValue::new();
+        for event in self.log.read().unwrap().iter() {
+            if !txids.contains(&event.txid()) {
+                continue;
+            }
+            if let Some(op) = patch_operation(event) {
+                ops = ops.push(Arc::new(op));
+            }
+        }
+
+        match serde_json::to_string(&ops) {
+            Ok(json) => Ok(json),
+            Err(err) => Error::json_error(err),
+        }
+    }
+
     pub fn print_history(&self) {
         let parents = self.parents.read().unwrap();
         let changed = self.changed.read().unwrap();
@@ -127,6 +255,16 @@ impl ChangeLogger {
         use NodeEvent::*;
 
         for event in self.log.read().unwrap().iter().rev() {
+            if let Conflict { txid, focus, value, .. } = event {
+                print!("[CF]#{:^2} ", txid);
+                match value {
+                    Some(value) => print!(" {:p}", value.as_ref()),
+                    None => print!(" {:16}", "removed"),
+                }
+                println!(" '{}'", focus.access_path());
+                continue;
+            }
+
             if let InternalLineUpdated { txid, focus, old_node, new_node } = event {
                 print!("[{}]#{:^2} ", "IL", txid);
                 print!(" {:p} => ", old_node.as_ref());
@@ -149,6 +287,7 @@ impl ChangeLogger {
                 InternalLineUpdated { txid, focus, old_node, new_node } => {
                     ("IL", txid, focus, new_node)
                 },
+                Conflict { .. } => continue,
             };
 
             print!("[{}]#{:^2} ", changed_type, txid);
@@ -3,8 +3,28 @@ mod cone;
 mod event;
 mod inode;
 mod domain;
+mod overlay;
+mod transaction;
+mod version;
+mod undo;
+mod writer;
+mod subscribe;
+mod stream;
+mod feed;
+mod cache;
+mod conflict;
+mod stamp;
 
-pub use log::ChangeLogger;
+pub use log::{ChangeLogger, NodeEvent};
 pub use cone::Cone;
 pub use domain::Domain;
+pub use overlay::Overlay;
+pub use transaction::{Transaction, Savepoint};
+pub use version::{Snapshot, SnapshotSpot};
+pub use subscribe::{Change, Subscription, SubscribeTarget};
+pub use stream::{StreamEvent, EventReceiver};
+#[cfg(feature = "futures")]
+pub use stream::EventStream;
+pub use cache::CacheStats;
+pub use conflict::{ConflictPolicy, ConflictResolver};
 pub use cone::get_item_node;
@@ -0,0 +1,83 @@
+use crate::spot::Spot;
+use crate::error::Error;
+
+use super::domain::Domain;
+
+
+/// 多个domain叠加而成的视图，后加入的层优先级更高。
+/// 读取时从最上层开始逐层查找，写入时需要指定目标层
+pub struct Overlay {
+    layers: Vec<(String, Domain)>,
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        Overlay {
+            layers: Vec::new(),
+        }
+    }
+
+    /// 在最上面加入一层，同名的层会被替换并保持原来的位置
+    pub fn push_layer(&mut self, name: &str, domain: Domain) {
+        match self.layers.iter_mut().find(|(layer_name, _)| layer_name == name) {
+            Some(layer) => layer.1 = domain,
+            None => self.layers.push((name.to_string(), domain)),
+        }
+    }
+
+    pub fn layer(&self, name: &str) -> Result<&Domain, Error> {
+        match self.layers.iter().find(|(layer_name, _)| layer_name == name) {
+            Some((_, domain)) => Ok(domain),
+            None => Error::no_such_layer(name),
+        }
+    }
+
+    /// 自上而下的层名
+    pub fn layer_names(&self) -> Vec<&str> {
+        self.layers.iter().rev().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// 自上而下查找path，返回提供该值的层名以及该层中的位置。
+    /// 所有层都没有该值时返回最上层的查找错误，没有任何层时返回EmptyOverlay
+    pub fn resolve(&self, path: &str) -> Result<(&str, Spot), Error> {
+        let mut first_error = None;
+
+        for (name, domain) in self.layers.iter().rev() {
+            match domain.navigate(path) {
+                Ok(spot) => return Ok((name.as_str(), spot)),
+                Err(err @ Error::NoSuchItem { .. })
+                | Err(err @ Error::WrongItemAccess { .. }) => {
+                    first_error.get_or_insert(err);
+                },
+                Err(err) => return Err(err),
+            }
+        }
+
+        match first_error {
+            Some(err) => Err(err),
+            None => Error::empty_overlay(),
+        }
+    }
+
+    #[inline]
+    pub fn navigate(&self, path: &str) -> Result<Spot, Error> {
+        self.resolve(path).map(|(_, spot)| spot)
+    }
+
+    /// 提供path处取值的层名
+    #[inline]
+    pub fn source_of(&self, path: &str) -> Result<&str, Error> {
+        self.resolve(path).map(|(name, _)| name)
+    }
+
+    /// 在指定层中定位，写入只影响该层
+    pub fn navigate_in(&self, layer: &str, path: &str) -> Result<Spot, Error> {
+        self.layer(layer)?.navigate(path)
+    }
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Overlay::new()
+    }
+}
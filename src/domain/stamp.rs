@@ -0,0 +1,145 @@
+use std::sync::{Arc, RwLock};
+
+use crate::error::Error;
+use crate::focus::{Focus, FocusLocator};
+
+use super::cache::is_ancestor_or_self;
+use super::cone::Cone;
+use super::domain::Domain;
+
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Stamp {
+    written: u64, // 直接写入该位置的txid，其下的全部位置随之改变
+    subtree: u64, // 该位置或其后代最近一次修改的txid
+}
+
+/// 按focus记录的版本戳。写入时记录写入的位置，
+/// 合并更新时随内部节点的更新向上传递到祖先。
+/// 整体替换或删除某个位置时，其下的版本戳不再需要，随之移除
+#[allow(clippy::mutable_key_type)] // Focus按指针比较和哈希
+pub(crate) struct VersionStamps {
+    stamps: RwLock<im::HashMap<Arc<Focus>, Stamp>>,
+    committed: RwLock<im::HashMap<Arc<Focus>, Stamp>>, // 事务之外可见的版本戳
+}
+
+#[allow(clippy::mutable_key_type)]
+impl VersionStamps {
+    pub(crate) fn new() -> Self {
+        VersionStamps {
+            stamps: RwLock::new(im::HashMap::new()),
+            committed: RwLock::new(im::HashMap::new()),
+        }
+    }
+
+    /// txid写入了focus处的节点，其父节点也随之更新为新的节点。
+    /// 后代的版本都不大于txid，由focus处的written代替
+    pub(crate) fn written(&self, focus: &Arc<Focus>, txid: u64) {
+        let mut stamps = self.stamps.write().unwrap();
+        remove_descendants(&mut stamps, focus);
+
+        let stamp = stamps.entry(focus.clone()).or_default();
+        stamp.written = stamp.written.max(txid);
+        stamp.subtree = stamp.subtree.max(txid);
+
+        if let Some(parent_focus) = focus.get_parent() {
+            let stamp = stamps.entry(parent_focus.clone()).or_default();
+            stamp.subtree = stamp.subtree.max(txid);
+        }
+    }
+
+    /// txid删除了focus处的节点。该位置没有版本，
+    /// 再次创建时由新的written代替
+    pub(crate) fn deleted(&self, focus: &Arc<Focus>, txid: u64) {
+        let mut stamps = self.stamps.write().unwrap();
+        remove_descendants(&mut stamps, focus);
+        stamps.remove(focus);
+
+        if let Some(parent_focus) = focus.get_parent() {
+            let stamp = stamps.entry(parent_focus.clone()).or_default();
+            stamp.subtree = stamp.subtree.max(txid);
+        }
+    }
+
+    /// focus的新节点已写入父节点，父节点的版本不小于它
+    pub(crate) fn propagate(&self, focus: &Arc<Focus>) {
+        let parent_focus = match focus.get_parent() {
+            Some(parent_focus) => parent_focus,
+            None => return,
+        };
+
+        let mut stamps = self.stamps.write().unwrap();
+        let subtree = match stamps.get(focus) {
+            Some(stamp) => stamp.subtree,
+            None => return,
+        };
+
+        let stamp = stamps.entry(parent_focus.clone()).or_default();
+        stamp.subtree = stamp.subtree.max(subtree);
+    }
+
+    /// focus处的版本：它和它的后代最近一次修改，或它的祖先被整体替换时的txid
+    pub(crate) fn version(&self, focus: &Arc<Focus>, committed: bool) -> u64 {
+        let stamps = if committed {
+            self.committed.read().unwrap().clone()
+        } else {
+            self.stamps.read().unwrap().clone()
+        };
+
+        let mut version = stamps.get(focus).map(|stamp| stamp.subtree).unwrap_or(0);
+        let mut current = Some(focus);
+        while let Some(node) = current {
+            if let Some(stamp) = stamps.get(node) {
+                version = version.max(stamp.written);
+            }
+            current = node.get_parent();
+        }
+        version
+    }
+
+    pub(crate) fn commit(&self) {
+        *self.committed.write().unwrap() = self.stamps.read().unwrap().clone();
+    }
+
+    pub(crate) fn snapshot(&self) -> im::HashMap<Arc<Focus>, Stamp> {
+        self.stamps.read().unwrap().clone()
+    }
+
+    pub(crate) fn restore(&self, stamps: im::HashMap<Arc<Focus>, Stamp>) {
+        *self.stamps.write().unwrap() = stamps;
+    }
+}
+
+/// 移除focus之下的版本戳。只检查已有的版本戳，不遍历focus树，
+/// 后者包含所有导航过的位置，可能远多于记录了版本戳的位置
+#[allow(clippy::mutable_key_type)]
+fn remove_descendants(stamps: &mut im::HashMap<Arc<Focus>, Stamp>, focus: &Arc<Focus>) {
+    // 版本戳持有它记录的focus，focus没有仍然存在的子focus时其下也没有版本戳
+    let mut has_children = false;
+    focus.foreach_directions(|_| has_children = true);
+    if !has_children {
+        return;
+    }
+
+    stamps.retain(|stamped, _| {
+        Arc::ptr_eq(stamped, focus) || !is_ancestor_or_self(focus, stamped)
+    });
+}
+
+impl Cone {
+    /// focus处的版本，其他线程尚未提交的修改不计入。
+    /// 在取得Spot时调用，不再合并待处理的更新：导航时已经合并过，
+    /// 由已有Spot派生的Spot与它所持有的节点一致
+    pub(crate) fn version_of_focus(&self, focus: &Arc<Focus>) -> u64 {
+        self.stamps.version(focus, !self.is_writer())
+    }
+}
+
+impl Domain {
+    /// path处节点的版本：它或它的后代最近一次被修改时的txid，可作为etag。
+    /// 版本相同说明期间没有修改；反过来不一定成立，版本可能偏大但不会偏小
+    pub fn version_of(&self, path: &str) -> Result<u64, Error> {
+        let spot = self.navigate(path)?;
+        Ok(spot.version())
+    }
+}
@@ -0,0 +1,189 @@
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, RecvError, SyncSender, TryRecvError, TrySendError};
+
+use crate::focus::FocusLocator;
+
+use super::cone::Cone;
+use super::domain::Domain;
+use super::log::NodeEvent;
+use super::subscribe::{Change, Subscription};
+
+
+/// 事件流中的一项
+#[derive(Clone)]
+pub enum StreamEvent {
+    /// 一个已提交的事件，txid由`NodeEvent::txid()`取得
+    Event(NodeEvent),
+    /// 缓冲区已满，此前有这么多个事件被丢弃
+    Lagged(u64),
+}
+
+impl ::std::fmt::Debug for StreamEvent {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        match self {
+            StreamEvent::Event(event) => fmt.write_fmt(format_args!("<Event #{} '{}'>",
+                event.txid(), event.focus().access_path())),
+            StreamEvent::Lagged(missed) => fmt.write_fmt(format_args!("<Lagged {}>", missed)),
+        }
+    }
+}
+
+enum SendFailure {
+    Full,
+    Disconnected,
+}
+
+/// 不阻塞写入线程的发送端
+trait EventSink: Send + 'static {
+    fn try_send(&mut self, item: StreamEvent) -> Result<(), SendFailure>;
+}
+
+impl EventSink for SyncSender<StreamEvent> {
+    fn try_send(&mut self, item: StreamEvent) -> Result<(), SendFailure> {
+        match SyncSender::try_send(self, item) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(SendFailure::Full),
+            Err(TrySendError::Disconnected(_)) => Err(SendFailure::Disconnected),
+        }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl EventSink for futures::channel::mpsc::Sender<StreamEvent> {
+    fn try_send(&mut self, item: StreamEvent) -> Result<(), SendFailure> {
+        match futures::channel::mpsc::Sender::try_send(self, item) {
+            Ok(()) => Ok(()),
+            Err(err) if err.is_full() => Err(SendFailure::Full),
+            Err(_) => Err(SendFailure::Disconnected),
+        }
+    }
+}
+
+/// 缓冲区满时丢弃事件并计数。计数不为0时之后的事件也一并丢弃，
+/// 直到接收端取完缓冲区中的事件并取走计数，事件因此不会乱序
+struct Forwarder<S: EventSink> {
+    sink: S,
+    missed: Arc<Mutex<u64>>,
+}
+
+impl<S: EventSink> Forwarder<S> {
+    fn forward(&mut self, event: &NodeEvent) {
+        let mut missed = self.missed.lock().unwrap();
+        if *missed > 0 {
+            *missed += 1;
+            return;
+        }
+
+        if let Err(SendFailure::Full) = self.sink.try_send(StreamEvent::Event(event.clone())) {
+            *missed = 1;
+        }
+    }
+}
+
+impl Cone {
+    /// 返回订阅的句柄，接收端持有它，drop时取消订阅
+    fn subscribe_sink<S: EventSink>(self: &Arc<Self>, sink: S, missed: Arc<Mutex<u64>>) -> Subscription {
+        let notifier = &self.notifier;
+        let id = notifier.next_subscriber_id();
+
+        let forwarder = Mutex::new(Forwarder { sink, missed });
+        notifier.add_subscriber(id, Vec::new(), Arc::new(move |change: &Change| {
+            forwarder.lock().unwrap().forward(change.event());
+        }));
+
+        Subscription::new(self, id)
+    }
+}
+
+
+/// event_stream的接收端。缓冲区中的事件取完之后，
+/// 如有事件因缓冲区已满被丢弃，先收到Lagged给出丢弃的个数
+pub struct EventReceiver {
+    receiver: Receiver<StreamEvent>,
+    missed: Arc<Mutex<u64>>,
+    _subscription: Subscription,
+}
+
+impl EventReceiver {
+    pub fn try_recv(&self) -> Result<StreamEvent, TryRecvError> {
+        let mut missed = self.missed.lock().unwrap();
+        match self.receiver.try_recv() {
+            Err(_) if *missed > 0 => Ok(StreamEvent::Lagged(std::mem::take(&mut *missed))),
+            result => result,
+        }
+    }
+
+    /// 阻塞直到收到下一项，domain被drop后返回RecvError
+    pub fn recv(&self) -> Result<StreamEvent, RecvError> {
+        match self.try_recv() {
+            Ok(item) => Ok(item),
+            Err(TryRecvError::Empty) => self.receiver.recv(),
+            Err(TryRecvError::Disconnected) => Err(RecvError),
+        }
+    }
+
+    pub fn try_iter(&self) -> impl Iterator<Item = StreamEvent> + '_ {
+        std::iter::from_fn(move || self.try_recv().ok())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = StreamEvent> + '_ {
+        std::iter::from_fn(move || self.recv().ok())
+    }
+}
+
+/// event_stream_async的接收端，Lagged的送出方式同EventReceiver
+#[cfg(feature = "futures")]
+pub struct EventStream {
+    receiver: futures::channel::mpsc::Receiver<StreamEvent>,
+    missed: Arc<Mutex<u64>>,
+    _subscription: Subscription,
+}
+
+#[cfg(feature = "futures")]
+impl futures::Stream for EventStream {
+    type Item = StreamEvent;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<StreamEvent>> {
+        use futures::StreamExt;
+        use std::task::Poll;
+
+        let this = self.get_mut();
+        let mut missed = this.missed.lock().unwrap();
+        match this.receiver.poll_next_unpin(cx) {
+            Poll::Ready(None) | Poll::Pending if *missed > 0 => {
+                Poll::Ready(Some(StreamEvent::Lagged(std::mem::take(&mut *missed))))
+            },
+            poll => poll,
+        }
+    }
+}
+
+impl Domain {
+    /// 已提交事件的通道，最多缓冲capacity个事件。
+    /// 消费者跟不上时多出的事件被丢弃，取完缓冲区后收到的Lagged给出丢弃的个数。
+    ///
+    /// 返回的不是`mpsc::Receiver`本身：写入线程不能阻塞在已满的通道上，
+    /// 此时Lagged也发不进通道，只能由接收端在取完缓冲区后给出，
+    /// 所以用EventReceiver包装，提供与`mpsc::Receiver`相同的recv、try_recv和iter
+    pub fn event_stream(&self, capacity: usize) -> EventReceiver {
+        let (sender, receiver) = mpsc::sync_channel(capacity.max(1));
+        let missed = Arc::new(Mutex::new(0));
+        let subscription = self.cone.subscribe_sink(sender, missed.clone());
+
+        EventReceiver { receiver, missed, _subscription: subscription }
+    }
+
+    /// 同event_stream，返回`futures::Stream`
+    #[cfg(feature = "futures")]
+    pub fn event_stream_async(&self, capacity: usize) -> EventStream {
+        // futures的通道为每个发送端额外保留一个位置
+        let (sender, receiver) = futures::channel::mpsc::channel(capacity.max(1) - 1);
+        let missed = Arc::new(Mutex::new(0));
+        let subscription = self.cone.subscribe_sink(sender, missed.clone());
+
+        EventStream { receiver, missed, _subscription: subscription }
+    }
+}
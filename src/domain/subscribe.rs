@@ -0,0 +1,273 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::Error;
+use crate::focus::{AccessKey, Focus, FocusLocator, FocusTurnTo};
+use crate::node::NodeValue;
+
+use super::cone::Cone;
+use super::domain::Domain;
+use super::log::NodeEvent;
+
+
+/// 一次已提交的修改，附带修改前的值
+#[derive(Clone)]
+pub struct Change {
+    event: NodeEvent,
+    old_value: Option<Arc<NodeValue>>,
+}
+
+impl Change {
+    pub fn event(&self) -> &NodeEvent {
+        &self.event
+    }
+
+    pub fn txid(&self) -> u64 {
+        self.event.txid()
+    }
+
+    pub fn focus(&self) -> &Arc<Focus> {
+        self.event.focus()
+    }
+
+    /// 修改前的值，新建的项没有旧值
+    pub fn old_value(&self) -> Option<&Arc<NodeValue>> {
+        self.old_value.as_ref()
+    }
+
+    /// 修改后的值，删除的项没有新值
+    pub fn new_value(&self) -> Option<&Arc<NodeValue>> {
+        match &self.event {
+            NodeEvent::ValueDeleted { .. } | NodeEvent::ListItemDeleted { .. } => None,
+            NodeEvent::RootUpdated { value, .. }
+            | NodeEvent::ValueCreated { value, .. }
+            | NodeEvent::ValueUpdated { value, .. }
+            | NodeEvent::ListItemInserted { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl ::std::fmt::Debug for Change {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        fmt.write_fmt(format_args!("<Change #{} '{}' {:?} => {:?}>",
+            self.txid(), self.focus().access_path(), self.old_value, self.new_value()))
+    }
+}
+
+
+/// 可以订阅的位置：路径中的`*`匹配任意一个key或下标
+pub trait SubscribeTarget {
+    fn segments(&self) -> Result<Vec<AccessKey>, Error>;
+}
+
+impl SubscribeTarget for &str {
+    fn segments(&self) -> Result<Vec<AccessKey>, Error> {
+        match Focus::new().turn_to(self) {
+            Ok(focus) => Ok(focus_segments(&focus)),
+            Err(err) => Err(Error::AccessPathError(err)),
+        }
+    }
+}
+
+impl SubscribeTarget for &Arc<Focus> {
+    fn segments(&self) -> Result<Vec<AccessKey>, Error> {
+        Ok(focus_segments(self))
+    }
+}
+
+fn focus_segments(focus: &Arc<Focus>) -> Vec<AccessKey> {
+    let mut segments = Vec::new();
+    let mut current = focus;
+    while let Some(parent) = current.get_parent() {
+        segments.push(current.get_access_key());
+        current = parent;
+    }
+    segments.reverse();
+    segments
+}
+
+/// focus等于pattern或在其之下，或者是pattern的祖先：
+/// 祖先处的修改整体替换了它的子树，pattern处的值也随之改变
+fn matches_pattern(pattern: &[AccessKey], focus: &Arc<Focus>) -> bool {
+    let segments = focus_segments(focus);
+
+    pattern.iter().zip(segments.iter()).all(|(expected, actual)| {
+        match expected {
+            AccessKey::Key(key) if key == "*" => true,
+            _ => expected == actual,
+        }
+    })
+}
+
+
+pub(crate) type Callback = Arc<dyn Fn(&Change) + Send + Sync>;
+
+/// 暂存未提交的修改，提交后按顺序分发给订阅者
+pub struct Notifier {
+    staged: Mutex<Vec<Change>>,
+    queue: Mutex<VecDeque<Change>>,
+    dispatching: Mutex<bool>,
+    subscribers: RwLock<Vec<(u64, Vec<AccessKey>, Callback)>>,
+    subscriber_id: AtomicU64,
+}
+
+impl Notifier {
+    pub(crate) fn new() -> Self {
+        Notifier {
+            staged: Mutex::new(Vec::new()),
+            queue: Mutex::new(VecDeque::new()),
+            dispatching: Mutex::new(false),
+            subscribers: RwLock::new(Vec::new()),
+            subscriber_id: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn next_subscriber_id(&self) -> u64 {
+        self.subscriber_id.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    pub(crate) fn add_subscriber(&self, id: u64, pattern: Vec<AccessKey>, callback: Callback) {
+        self.subscribers.write().unwrap().push((id, pattern, callback));
+    }
+
+    pub(crate) fn remove_subscriber(&self, id: u64) {
+        self.subscribers.write().unwrap().retain(|(subscriber, _, _)| *subscriber != id);
+    }
+
+    pub(crate) fn stage(&self, event: &NodeEvent, old_value: Option<Arc<NodeValue>>) {
+        let change = Change { event: event.clone(), old_value };
+        self.staged.lock().unwrap().push(change);
+    }
+
+    pub(crate) fn staged_len(&self) -> usize {
+        self.staged.lock().unwrap().len()
+    }
+
+    /// 合并时修改的实际位置与记录时不同，换成修正后的事件；None时丢弃该修改
+    pub(crate) fn amend_staged(&self, event: &NodeEvent, amended: Option<&NodeEvent>) {
+        let mut staged = self.staged.lock().unwrap();
+        let index = match staged.iter().rposition(|change| &change.event == event) {
+            Some(index) => index,
+            None => return,
+        };
+
+        match amended {
+            Some(amended) => {
+                if let NodeEvent::ListItemInserted { .. } = amended {
+                    staged[index].old_value = None;
+                }
+                staged[index].event = amended.clone();
+            },
+            None => {
+                staged.remove(index);
+            },
+        }
+    }
+
+    /// 丢弃回滚掉的修改
+    pub(crate) fn truncate_staged(&self, len: usize) {
+        self.staged.lock().unwrap().truncate(len);
+    }
+
+    /// 暂存的修改已提交，移入分发队列
+    pub(crate) fn commit_staged(&self) {
+        let mut staged = self.staged.lock().unwrap();
+        self.queue.lock().unwrap().extend(staged.drain(..));
+    }
+
+    /// 同一时间只有一个线程分发，其他线程提交的修改由它按顺序一并送出。
+    /// 回调中可以再次修改domain，新的修改会排在队列之后
+    pub(crate) fn dispatch(&self) {
+        {
+            let mut dispatching = self.dispatching.lock().unwrap();
+            if *dispatching {
+                return;
+            }
+            *dispatching = true;
+        }
+        // 回调panic时也要复位，否则之后的通知都不会再分发
+        let _guard = DispatchGuard(&self.dispatching);
+
+        loop {
+            let change = {
+                let mut dispatching = self.dispatching.lock().unwrap();
+                let mut queue = self.queue.lock().unwrap();
+                match queue.pop_front() {
+                    Some(change) => change,
+                    None => {
+                        *dispatching = false;
+                        return;
+                    },
+                }
+            };
+
+            let callbacks = self.subscribers.read().unwrap().iter()
+                .filter(|(_, pattern, _)| matches_pattern(pattern, change.focus()))
+                .map(|(_, _, callback)| callback.clone())
+                .collect::<Vec<Callback>>();
+
+            for callback in callbacks {
+                callback(&change);
+            }
+        }
+    }
+}
+
+struct DispatchGuard<'a>(&'a Mutex<bool>);
+
+impl Drop for DispatchGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = false;
+    }
+}
+
+
+/// 订阅的句柄，drop时取消订阅
+pub struct Subscription {
+    cone: Weak<Cone>,
+    id: u64,
+}
+
+impl Subscription {
+    pub(crate) fn new(cone: &Arc<Cone>, id: u64) -> Self {
+        Subscription {
+            cone: Arc::downgrade(cone),
+            id,
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(cone) = self.cone.upgrade() {
+            cone.notifier.remove_subscriber(self.id);
+        }
+    }
+}
+
+impl Domain {
+    /// 订阅target及其之下的修改，每次修改提交后调用callback。
+    /// target的祖先被整体替换或删除时，送出的是祖先处的修改。
+    /// 事务中的修改在提交后送出，回滚的修改不会送出；
+    /// callback可能在进行写入的其他线程中被调用
+    pub fn subscribe<T, F>(&self, target: T, callback: F) -> Result<Subscription, Error>
+    where
+        T: SubscribeTarget,
+        F: Fn(&Change) + Send + Sync + 'static,
+    {
+        let pattern = target.segments()?;
+
+        let notifier = &self.cone.notifier;
+        let id = notifier.next_subscriber_id();
+        notifier.add_subscriber(id, pattern, Arc::new(callback));
+
+        Ok(Subscription::new(&self.cone, id))
+    }
+
+    /// 当前的订阅者个数，包括event_stream的接收端
+    pub fn subscriber_count(&self) -> usize {
+        self.cone.notifier.subscribers.read().unwrap().len()
+    }
+}
@@ -0,0 +1,221 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+use crate::error::Error;
+use crate::focus::Focus;
+use crate::node::NodeValue;
+use crate::spot::Spot;
+
+use super::cone::Cone;
+use super::domain::Domain;
+use super::log::PendingUpdate;
+use super::stamp::Stamp;
+use super::writer::thread_no;
+
+
+/// 事务开始时的状态。节点不可变，恢复时只需换回根节点和待处理的更新
+pub(crate) struct RestorePoint {
+    root_node: Arc<NodeValue>,
+    pending: HashMap<Arc<Focus>, Vec<PendingUpdate>>,
+    log_len: usize,
+    staged_len: usize,
+    txid_max: u64,
+    stamps: im::HashMap<Arc<Focus>, Stamp>,
+}
+
+impl Cone {
+    pub(crate) fn restore_point(&self) -> RestorePoint {
+        let logger = &self.logger;
+
+        RestorePoint {
+            root_node: self.root_node.load_full(),
+            pending: logger.pending.read().unwrap().clone(),
+            log_len: logger.log.read().unwrap().len(),
+            staged_len: self.notifier.staged_len(),
+            txid_max: *logger.txid_max.read().unwrap(),
+            stamps: self.stamps.snapshot(),
+        }
+    }
+
+    /// 丢弃快照之后的全部修改，日志截断到快照时的长度
+    pub(crate) fn restore(&self, restore_point: RestorePoint) {
+        let logger = &self.logger;
+
+        *logger.pending.write().unwrap() = restore_point.pending;
+        logger.log.write().unwrap().truncate(restore_point.log_len);
+        self.notifier.truncate_staged(restore_point.staged_len);
+        *logger.txid_max.write().unwrap() = restore_point.txid_max;
+        self.stamps.restore(restore_point.stamps);
+
+        // 已提交的根节点没有改变，缓存仍然有效
+        self.remount_root(restore_point.root_node);
+    }
+
+    /// 开启事务，已在事务中时加入外层事务
+    fn begin_transaction(&self) -> TransactionGuard<'_> {
+        let logger = &self.logger;
+        let restore_point = self.restore_point();
+
+        let current = *logger.transaction.read().unwrap();
+        let (txid, outermost) = match current {
+            Some(txid) => (txid, false),
+            None => {
+                let txid = logger.new_txid();
+                *logger.transaction.write().unwrap() = Some(txid);
+                logger.transaction_thread.store(thread_no(), Ordering::SeqCst);
+                (txid, true)
+            },
+        };
+
+        TransactionGuard {
+            cone: self,
+            txid,
+            outermost,
+            restore_point: Some(restore_point),
+        }
+    }
+
+    fn end_transaction(&self) {
+        *self.logger.transaction.write().unwrap() = None;
+        self.logger.transaction_thread.store(0, Ordering::SeqCst);
+    }
+}
+
+
+/// 进行中的事务。未commit就被drop时（返回错误或panic）回滚到事务开始前
+struct TransactionGuard<'a> {
+    cone: &'a Cone,
+    txid: u64,
+    outermost: bool,
+    restore_point: Option<RestorePoint>, // commit之后为None
+}
+
+impl<'a> TransactionGuard<'a> {
+    fn commit(mut self) {
+        let restore_point = self.restore_point.take().unwrap();
+        if !self.outermost {
+            return;
+        }
+
+        let cone = self.cone;
+        cone.get_root_node();
+        cone.end_transaction();
+
+        // 没有任何写入时不占用txid
+        if cone.logger.log.read().unwrap().len() == restore_point.log_len {
+            *cone.logger.txid_max.write().unwrap() = restore_point.txid_max;
+        }
+        cone.commit_root();
+    }
+}
+
+impl<'a> Drop for TransactionGuard<'a> {
+    fn drop(&mut self) {
+        if let Some(restore_point) = self.restore_point.take() {
+            self.cone.restore(restore_point);
+            if self.outermost {
+                self.cone.end_transaction();
+            }
+        }
+    }
+}
+
+/// 事务中的写入共用同一个txid，通过它取得的Spot与domain上的一致
+pub struct Transaction<'a> {
+    domain: &'a Domain,
+    txid: u64,
+    savepoints: RefCell<Vec<(u64, RestorePoint)>>,
+    savepoint_id: Cell<u64>,
+}
+
+/// 事务中的保存点，只能由创建它的事务释放或回滚一次
+#[derive(Debug)]
+pub struct Savepoint {
+    id: u64,
+}
+
+impl<'a> Transaction<'a> {
+    fn new(domain: &'a Domain, txid: u64) -> Self {
+        Transaction {
+            domain,
+            txid,
+            savepoints: RefCell::new(Vec::new()),
+            savepoint_id: Cell::new(0),
+        }
+    }
+
+    pub fn txid(&self) -> u64 {
+        self.txid
+    }
+
+    pub fn root(&self) -> Spot {
+        self.domain.root()
+    }
+
+    #[inline]
+    pub fn navigate(&self, path: &str) -> Result<Spot, Error> {
+        self.domain.navigate(path)
+    }
+
+    /// 记录当前状态，之后可以单独回滚到这里。保存点可以嵌套
+    pub fn savepoint(&self) -> Savepoint {
+        let id = self.savepoint_id.get() + 1;
+        self.savepoint_id.set(id);
+
+        let restore_point = self.domain.cone.restore_point();
+        self.savepoints.borrow_mut().push((id, restore_point));
+
+        Savepoint { id }
+    }
+
+    /// 保留保存点之后的修改，并释放它以及在它之后创建的保存点
+    pub fn release(&self, savepoint: Savepoint) -> Result<(), Error> {
+        self.take_savepoint(&savepoint).map(|_| ())
+    }
+
+    /// 撤销保存点之后的全部修改，包括日志和待处理的更新；
+    /// 在它之后创建的保存点一并失效，事务本身继续进行
+    pub fn rollback_to(&self, savepoint: Savepoint) -> Result<(), Error> {
+        let restore_point = self.take_savepoint(&savepoint)?;
+        self.domain.cone.restore(restore_point);
+        Ok(())
+    }
+
+    fn take_savepoint(&self, savepoint: &Savepoint) -> Result<RestorePoint, Error> {
+        let mut savepoints = self.savepoints.borrow_mut();
+
+        match savepoints.iter().position(|(id, _)| *id == savepoint.id) {
+            Some(index) => Ok(savepoints.drain(index..).next().unwrap().1),
+            None => Error::no_such_savepoint(savepoint.id),
+        }
+    }
+}
+
+impl Domain {
+    /// 在一个事务中执行func：其中的全部写入使用同一个txid，成功后一次性合并到根节点；
+    /// func返回错误或panic时根节点、待处理的更新和日志都恢复到事务开始前。
+    /// 嵌套调用加入外层事务，但出错时只回滚自己的部分
+    pub fn transaction<T, F>(&self, func: F) -> Result<T, Error>
+    where
+        F: FnOnce(&Transaction) -> Result<T, Error>,
+    {
+        let cone = &self.cone;
+
+        // 其他线程的写入等到事务结束后再进行
+        let _writer = cone.lock_writer();
+
+        // 事务之前的写入先合并到根节点，成为单独的版本
+        if cone.logger.transaction.read().unwrap().is_none() {
+            cone.get_root_node();
+        }
+
+        // 先于_writer释放，回滚在持有写入锁时完成
+        let guard = cone.begin_transaction();
+
+        let result = func(&Transaction::new(self, guard.txid))?;
+        guard.commit();
+        Ok(result)
+    }
+}
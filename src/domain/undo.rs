@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+use crate::error::Error;
+use crate::node::NodeValue;
+
+use super::domain::Domain;
+
+
+/// 撤销和重做的栈，每个版本（通常即一个事务）是一组修改
+pub struct UndoHistory {
+    undo: VecDeque<Arc<NodeValue>>,
+    redo: Vec<Arc<NodeValue>>,
+    replaying: bool,
+}
+
+impl UndoHistory {
+    pub(crate) fn new() -> Self {
+        UndoHistory {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            replaying: false,
+        }
+    }
+
+    /// 记录一组新的修改之前的根节点；非撤销/重做产生的修改使重做失效
+    pub(crate) fn record(&mut self, old_root: Arc<NodeValue>, limit: Option<usize>) {
+        if self.replaying {
+            return;
+        }
+
+        self.undo.push_back(old_root);
+        self.redo.clear();
+        self.truncate(limit);
+    }
+
+    /// limit是保留的版本数（含当前版本），可撤销的修改不超过其中的旧版本数，丢弃最早的
+    pub(crate) fn truncate(&mut self, limit: Option<usize>) {
+        if let Some(limit) = limit {
+            while self.undo.len() > limit.saturating_sub(1) {
+                self.undo.pop_front();
+            }
+        }
+    }
+}
+
+/// 撤销/重做期间的写入不记入历史，离开作用域时（包括出错或panic）复位
+struct ReplayGuard<'a>(&'a RwLock<UndoHistory>);
+
+impl<'a> ReplayGuard<'a> {
+    fn new(history: &'a RwLock<UndoHistory>) -> Self {
+        history.write().unwrap().replaying = true;
+        ReplayGuard(history)
+    }
+}
+
+impl Drop for ReplayGuard<'_> {
+    fn drop(&mut self) {
+        self.0.write().unwrap_or_else(|err| err.into_inner()).replaying = false;
+    }
+}
+
+impl Domain {
+    pub fn can_undo(&self) -> bool {
+        self.cone.get_root_node();
+        !self.cone.undo_history.read().unwrap().undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.cone.get_root_node();
+        !self.cone.undo_history.read().unwrap().redo.is_empty()
+    }
+
+    /// 撤销最近的一组修改。之前的根节点经由正常的写入路径重新挂载，
+    /// 日志中记为新的变更。没有可撤销的修改时返回false
+    pub fn undo(&self) -> Result<bool, Error> {
+        let _writer = self.cone.lock_writer();
+        let current = self.cone.get_root_node();
+
+        let previous = match self.cone.undo_history.write().unwrap().undo.pop_back() {
+            Some(previous) => previous,
+            None => return Ok(false),
+        };
+
+        self.replay(previous)?;
+        self.cone.undo_history.write().unwrap().redo.push(current);
+        Ok(true)
+    }
+
+    /// 重做最近撤销的一组修改，撤销之后有新的修改时不能再重做
+    pub fn redo(&self) -> Result<bool, Error> {
+        let _writer = self.cone.lock_writer();
+        let current = self.cone.get_root_node();
+
+        let next = match self.cone.undo_history.write().unwrap().redo.pop() {
+            Some(next) => next,
+            None => return Ok(false),
+        };
+
+        self.replay(next)?;
+        let limit = *self.cone.version_limit.read().unwrap();
+        let mut history = self.cone.undo_history.write().unwrap();
+        history.undo.push_back(current);
+        history.truncate(limit);
+        Ok(true)
+    }
+
+    fn replay(&self, root: Arc<NodeValue>) -> Result<(), Error> {
+        let _replaying = ReplayGuard::new(&self.cone.undo_history);
+        self.root().set_value_node(root).map(|_| ())
+    }
+}
@@ -0,0 +1,220 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::diff::DiffOp;
+use crate::error::Error;
+use crate::focus::{AccessKey, Focus};
+use crate::node::NodeValue;
+use crate::spot::Spot;
+
+use super::cone::Cone;
+use super::domain::Domain;
+
+
+/// 固定在某个版本上的只读视图。它持有当时的根节点，
+/// 之后对domain的修改不会影响它
+pub struct Snapshot {
+    txid: u64,
+    cone: Arc<Cone>,
+}
+
+impl Snapshot {
+    fn new(txid: u64, root: Arc<NodeValue>) -> Self {
+        Snapshot {
+            txid,
+            cone: Cone::detached(root),
+        }
+    }
+
+    pub fn txid(&self) -> u64 {
+        self.txid
+    }
+
+    pub fn root(&self) -> SnapshotSpot {
+        SnapshotSpot(Spot::new(
+            self.cone.clone(),
+            self.cone.root_focus.clone(),
+            self.cone.root_node.load_full(),
+            None,
+        ))
+    }
+
+    #[inline]
+    pub fn navigate(&self, path: &str) -> Result<SnapshotSpot, Error> {
+        self.root().navigate(path)
+    }
+}
+
+/// 快照中的位置，只提供读取的方法
+pub struct SnapshotSpot(Spot);
+
+impl SnapshotSpot {
+    #[inline]
+    pub fn get_focus(&self) -> &Arc<Focus> {
+        self.0.get_focus()
+    }
+
+    #[inline]
+    pub fn get_node(&self) -> &Arc<NodeValue> {
+        self.0.get_node()
+    }
+
+    pub fn focus<K: Into<AccessKey>>(self, access_key: K) -> Result<SnapshotSpot, Error> {
+        self.0.focus(access_key).map(SnapshotSpot)
+    }
+
+    pub fn navigate(&self, path: &str) -> Result<SnapshotSpot, Error> {
+        self.0.navigate(path).map(SnapshotSpot)
+    }
+
+    #[inline]
+    pub fn keys(&self) -> Result<impl Iterator<Item = AccessKey>, Error> {
+        self.0.keys()
+    }
+
+    pub fn items(&self) -> Result<impl Iterator<Item = (AccessKey, SnapshotSpot)>, Error> {
+        let items = self.0.items()?;
+        Ok(items.map(|(access_key, item)| (access_key, SnapshotSpot(item))))
+    }
+
+    #[inline]
+    pub fn children(&self) -> Result<impl Iterator<Item = (AccessKey, SnapshotSpot)>, Error> {
+        self.items()
+    }
+
+    #[inline]
+    pub fn len(&self) -> Result<isize, Error> {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        self.0.is_empty()
+    }
+
+    #[inline]
+    pub fn is_none(&self) -> bool {
+        self.0.is_none()
+    }
+
+    #[inline]
+    pub fn is_integer(&self) -> bool {
+        self.0.is_integer()
+    }
+
+    #[inline]
+    pub fn is_bool(&self) -> bool {
+        self.0.is_bool()
+    }
+
+    #[inline]
+    pub fn is_float(&self) -> bool {
+        self.0.is_float()
+    }
+
+    #[inline]
+    pub fn is_number(&self) -> bool {
+        self.0.is_number()
+    }
+
+    #[inline]
+    pub fn is_string(&self) -> bool {
+        self.0.is_string()
+    }
+
+    #[inline]
+    pub fn is_map(&self) -> bool {
+        self.0.is_map()
+    }
+
+    #[inline]
+    pub fn is_list(&self) -> bool {
+        self.0.is_list()
+    }
+
+    #[inline]
+    pub fn as_bool(&self) -> Result<bool, Error> {
+        self.0.as_bool()
+    }
+
+    #[inline]
+    pub fn as_i64(&self) -> Result<i64, Error> {
+        self.0.as_i64()
+    }
+
+    #[inline]
+    pub fn as_f64(&self) -> Result<f64, Error> {
+        self.0.as_f64()
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> Result<&str, Error> {
+        self.0.as_str()
+    }
+
+    #[inline]
+    pub fn try_into<T>(&self) -> Result<T, Error>
+        where T: for<'a> TryFrom<&'a Spot, Error = Error>
+    {
+        self.0.try_into()
+    }
+
+    #[inline]
+    pub fn to_json(&self) -> Result<String, Error> {
+        self.0.to_json()
+    }
+
+    #[inline]
+    pub fn to_json_pretty(&self) -> Result<String, Error> {
+        self.0.to_json_pretty()
+    }
+
+    /// 从该位置变化到other位置所需的操作，可用于比较两个版本
+    #[inline]
+    pub fn diff(&self, other: &SnapshotSpot) -> Vec<DiffOp> {
+        self.0.diff(&other.0)
+    }
+}
+
+impl Domain {
+    /// 当前根节点及其txid的只读快照
+    pub fn snapshot(&self) -> Snapshot {
+        let root = self.cone.get_root_node();
+        Snapshot::new(self.cone.logger.current_txid(), root)
+    }
+
+    /// 保留的各个版本的txid，从旧到新
+    pub fn versions(&self) -> Vec<u64> {
+        self.cone.get_root_node();
+
+        let versions = self.cone.versions.read().unwrap();
+        versions.iter().map(|(txid, _)| *txid).collect()
+    }
+
+    /// 读取txid时的整棵树，即不晚于txid的最近一个保留的版本。
+    /// txid早于保留的最旧版本或晚于当前txid时返回NoSuchVersion
+    pub fn checkout(&self, txid: u64) -> Result<Snapshot, Error> {
+        self.cone.get_root_node();
+        if txid > self.cone.logger.current_txid() {
+            return Error::no_such_version(txid);
+        }
+
+        let versions = self.cone.versions.read().unwrap();
+        match versions.iter().rev().find(|(version, _)| *version <= txid) {
+            Some((version, root)) => Ok(Snapshot::new(*version, root.clone())),
+            None => Error::no_such_version(txid),
+        }
+    }
+
+    /// 最多保留limit个最新的版本，当前版本总会保留
+    pub fn retain_versions(&self, limit: usize) {
+        let limit = limit.max(1);
+        *self.cone.version_limit.write().unwrap() = Some(limit);
+
+        let mut versions = self.cone.versions.write().unwrap();
+        while versions.len() > limit {
+            versions.pop_front();
+        }
+        self.cone.undo_history.write().unwrap().truncate(Some(limit));
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+use super::cone::Cone;
+
+
+static NEXT_THREAD_NO: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_NO: u64 = NEXT_THREAD_NO.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 当前线程的编号，从1开始，0表示没有线程
+#[inline]
+pub(crate) fn thread_no() -> u64 {
+    THREAD_NO.with(|no| *no)
+}
+
+
+/// 写入锁，同一线程可以重入。事务在整个过程中持有它，
+/// 其他线程的写入因此排在事务之后。读取不取得锁：
+/// 持有锁的线程读取自己的根节点，其他线程读取已提交的根节点
+pub(crate) struct WriterGuard<'a> {
+    cone: &'a Cone,
+}
+
+impl Cone {
+    pub(crate) fn lock_writer(&self) -> WriterGuard<'_> {
+        let current = thread::current().id();
+
+        let mut writer = self.writer.lock().unwrap();
+        loop {
+            match *writer {
+                None => {
+                    *writer = Some((current, 1));
+                    self.writer_thread.store(thread_no(), Ordering::SeqCst);
+                    break;
+                },
+                Some((owner, ref mut depth)) if owner == current => {
+                    *depth += 1;
+                    break;
+                },
+                Some(_) => {
+                    writer = self.writer_released.wait(writer).unwrap();
+                },
+            }
+        }
+
+        WriterGuard { cone: self }
+    }
+
+    /// 当前线程是否持有写入锁。不需要加锁：只有持有者自己会写入它的编号
+    #[inline]
+    pub(crate) fn is_writer(&self) -> bool {
+        self.writer_thread.load(Ordering::SeqCst) == thread_no()
+    }
+}
+
+impl<'a> Drop for WriterGuard<'a> {
+    fn drop(&mut self) {
+        let cone = self.cone;
+
+        {
+            let mut writer = cone.writer.lock().unwrap();
+            match *writer {
+                Some((_, ref mut depth)) if *depth > 1 => {
+                    *depth -= 1;
+                    return;
+                },
+                Some(_) => {},
+                None => return,
+            }
+        }
+
+        // 事务之外的写入在释放锁之前合并到根节点并提交，
+        // 其他线程因此只需读取已提交的根节点；修改按写入顺序排队
+        if cone.logger.transaction.read().unwrap().is_none() {
+            cone.solve_pending_at(&cone.root_focus);
+            cone.logger.mark_committed();
+            cone.notifier.commit_staged();
+        }
+
+        {
+            let mut writer = cone.writer.lock().unwrap();
+            cone.writer_thread.store(0, Ordering::SeqCst);
+            *writer = None;
+            cone.writer_released.notify_all();
+        }
+
+        // 回调在锁外调用，其中可以再次写入
+        cone.notifier.dispatch();
+    }
+}
@@ -3,6 +3,7 @@
 use crate::focus::AccessPathError;
 use std::sync::Arc;
 use crate::focus::{Focus, AccessKey, FocusLocator};
+use crate::node::NodeValue;
 
 
 #[derive(Debug, PartialEq)]
@@ -26,6 +27,34 @@ pub enum Error {
         focus: Arc<Focus>,
     },
     AccessPathError(AccessPathError),
+    JsonError(String),
+    InvalidPatch(String),
+    PatchTestFailed {
+        focus: Arc<Focus>,
+    },
+    MergeConflict {
+        conflicts: Vec<Arc<Focus>>,
+    },
+    NoSuchLayer(String),
+    EmptyOverlay,
+    TypeMismatch {
+        focus: Arc<Focus>,
+        expected: &'static str,
+        found: &'static str,
+    },
+    NoSuchSavepoint(u64),
+    NoSuchVersion(u64),
+    NoSuchCursor(String),
+    Compacted {
+        txid: u64,
+        compacted: u64,
+    },
+    InTransaction,
+    Conflict {
+        focus: Arc<Focus>,
+        ours: Arc<NodeValue>,
+        theirs: Arc<NodeValue>,
+    },
 
 
     // MismatchedType,
@@ -52,6 +81,50 @@ impl Error {
     pub fn should_be_list<T>(parent_focus: &Arc<Focus>) -> Result<T, Error> {
         Err(Error::ListRequired {focus: parent_focus.clone()})
     }
+
+    pub fn json_error<T>(err: serde_json::Error) -> Result<T, Error> {
+        Err(Error::JsonError(err.to_string()))
+    }
+
+    pub fn merge_conflict<T>(conflicts: Vec<Arc<Focus>>) -> Result<T, Error> {
+        Err(Error::MergeConflict {conflicts})
+    }
+
+    pub fn no_such_layer<T>(name: &str) -> Result<T, Error> {
+        Err(Error::NoSuchLayer(name.to_string()))
+    }
+
+    pub fn empty_overlay<T>() -> Result<T, Error> {
+        Err(Error::EmptyOverlay)
+    }
+
+    pub fn type_mismatch<T>(focus: &Arc<Focus>, expected: &'static str, found: &'static str) -> Result<T, Error> {
+        Err(Error::TypeMismatch {focus: focus.clone(), expected, found})
+    }
+
+    pub fn no_such_savepoint<T>(id: u64) -> Result<T, Error> {
+        Err(Error::NoSuchSavepoint(id))
+    }
+
+    pub fn no_such_version<T>(txid: u64) -> Result<T, Error> {
+        Err(Error::NoSuchVersion(txid))
+    }
+
+    pub fn no_such_cursor<T>(name: &str) -> Result<T, Error> {
+        Err(Error::NoSuchCursor(name.to_string()))
+    }
+
+    pub fn compacted<T>(txid: u64, compacted: u64) -> Result<T, Error> {
+        Err(Error::Compacted {txid, compacted})
+    }
+
+    pub fn in_transaction<T>() -> Result<T, Error> {
+        Err(Error::InTransaction)
+    }
+
+    pub fn conflict<T>(focus: &Arc<Focus>, ours: Arc<NodeValue>, theirs: Arc<NodeValue>) -> Result<T, Error> {
+        Err(Error::Conflict {focus: focus.clone(), ours, theirs})
+    }
 }
 
 
@@ -93,6 +166,51 @@ impl std::fmt::Display for Error {
             },
             AccessPathError(err) => {
                 write!(f, "{}", err)
+            },
+            JsonError(msg) => {
+                write!(f, "JSON error: {}", msg)
+            },
+            InvalidPatch(msg) => {
+                write!(f, "Invalid JSON Patch: {}", msg)
+            },
+            PatchTestFailed {focus} => {
+                write!(f, "JSON Patch test failed at {}", focus.access_path())
+            },
+            MergeConflict {conflicts} => {
+                let paths = conflicts.iter()
+                    .map(|focus| focus.access_path())
+                    .collect::<Vec<String>>();
+                write!(f, "Merge conflicts at {}", paths.join(", "))
+            },
+            NoSuchLayer(name) => {
+                write!(f, "No such layer '{}' in overlay", name)
+            },
+            EmptyOverlay => {
+                write!(f, "The overlay has no layers")
+            },
+            TypeMismatch {focus, expected, found} => {
+                write!(f, "The node '{}' should be {}, but found {}", 
+                                focus.access_path(), expected, found)
+            },
+            NoSuchSavepoint(id) => {
+                write!(f, "The savepoint #{} has been released or rolled back", id)
+            },
+            NoSuchVersion(txid) => {
+                write!(f, "No retained version at txid {}", txid)
+            },
+            NoSuchCursor(name) => {
+                write!(f, "No such change feed cursor '{}'", name)
+            },
+            Compacted {txid, compacted} => {
+                write!(f, "Changes after txid {} have been compacted up to txid {}", 
+                                txid, compacted)
+            },
+            InTransaction => {
+                write!(f, "The operation is not allowed inside a transaction")
+            },
+            Conflict {focus, ..} => {
+                write!(f, "Conflicting write at {}, it has been changed by another writer", 
+                                focus.access_path())
             }
             // UnexpectedCharacter {
             //     ref ch,
@@ -118,6 +236,19 @@ impl std::error::Error for Error {
             CollectionRequired {..} => "The node should be a Map or List",
             ListRequired {..} => "The node should be a List",
             AccessPathError(_) => "access path error",
+            JsonError(_) => "JSON error",
+            InvalidPatch(_) => "Invalid JSON Patch",
+            PatchTestFailed {..} => "JSON Patch test failed",
+            MergeConflict {..} => "Merge conflicts",
+            NoSuchLayer(_) => "No such layer in overlay",
+            EmptyOverlay => "The overlay has no layers",
+            TypeMismatch {..} => "Mismatched value type",
+            NoSuchSavepoint(_) => "No such savepoint in transaction",
+            NoSuchVersion(_) => "No such retained version",
+            NoSuchCursor(_) => "No such change feed cursor",
+            Compacted {..} => "Requested changes have been compacted",
+            InTransaction => "Not allowed inside a transaction",
+            Conflict {..} => "Conflicting concurrent write",
 
             // UnexpectedEndOfJson        => "Unexpected end of JSON",
             // ExceededDepthLimit         => "Exceeded depth limit",
@@ -38,7 +38,14 @@ impl Drop for Focus {
     fn drop(&mut self) {
         if let Some(ref parent_focus) = self.parent_focus {
             let mut directions = parent_focus.directions.write().unwrap();
-            directions.remove(&self.access_key);
+            // 其他线程可能已经为同一个key创建了新的focus，只移除指向自己的项
+            let is_self = match directions.get(&self.access_key) {
+                Some(direction) => std::ptr::eq(direction.as_ptr(), self),
+                None => false,
+            };
+            if is_self {
+                directions.remove(&self.access_key);
+            }
             // println!("Drop {:?}", self.access_key);
         }
     }
@@ -19,6 +19,9 @@ pub trait FocusLocator: FocusTurnTo {
 
     fn access_path(&self) -> String;
 
+    /// RFC 6901形式的路径，根节点为空串
+    fn json_pointer(&self) -> String;
+
     fn get_direction_keys<'a>(&'a self) -> Vec<AccessKey>;
 
     fn foreach_directions<F>(&self, f: F) where F: FnMut(&Arc<Focus>);
@@ -138,6 +141,24 @@ impl FocusLocator for Arc<Focus> {
         path
     }
 
+    fn json_pointer(&self) -> String {
+        let mut segments = self.ancestors()
+            .filter_map(|focus| match focus.access_key {
+                AccessKey::Key(ref key) => Some(key.replace('~', "~0").replace('/', "~1")),
+                AccessKey::Index(index) => Some(index.to_string()),
+                AccessKey::None => None,
+            })
+            .collect::<Vec<String>>();
+        segments.reverse();
+
+        let mut pointer = String::new();
+        for segment in segments {
+            pointer.push('/');
+            pointer.push_str(&segment);
+        }
+        pointer
+    }
+
     fn get_direction_keys<'a>(&'a self) -> Vec<AccessKey> {
 
         let directions = self.directions.read().unwrap();
@@ -139,6 +139,15 @@ mod tests {
             "/#1#2#-3#4"
         );
     }
+    #[test]
+    fn json_pointer() {
+
+        let f = Focus::new();
+        assert_eq!(f.json_pointer(), "");
+        assert_eq!(f.turn_to("/a1/b2#123/c3").ok().unwrap().json_pointer(), "/a1/b2/123/c3");
+        assert_eq!(f.focus("a/b").focus("m~n").focus(0).json_pointer(), "/a~1b/m~0n/0");
+    }
+
     #[test]
     fn foreach_directions() {
 
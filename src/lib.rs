@@ -3,9 +3,20 @@ mod node;
 pub mod focus;
 mod spot;
 mod domain;
+mod diff;
+mod patch;
+mod merge;
 
 mod error;
 
 pub use error::Error;
-pub use domain::Domain;
+pub use domain::{Domain, Overlay, Transaction, Savepoint, Snapshot, SnapshotSpot};
+pub use domain::{NodeEvent, Change, Subscription, SubscribeTarget, StreamEvent, EventReceiver, CacheStats};
+#[cfg(feature = "futures")]
+pub use domain::EventStream;
+pub use domain::{ConflictPolicy, ConflictResolver};
+pub use spot::{Spot, Visitor, Walk};
+pub use diff::DiffOp;
+pub use merge::{MergeStrategy, ListMerge, ScalarMerge};
+pub use node::{NodeValue, MapValue, ListValue, StructuralKey};
 
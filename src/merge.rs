@@ -0,0 +1,300 @@
+// 分层深度合并
+use std::sync::Arc;
+
+use crate::focus::{Focus, FocusLocator};
+use crate::node::{NodeValue, ListValue, MapValue};
+
+
+/// 两边都是列表时的合并方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListMerge {
+    /// 右边的项追加到左边列表之后
+    Append,
+    /// 右边的列表整体替换左边
+    Replace,
+    /// 相同下标的项递归合并，右边多出的项追加在后
+    ByIndex,
+}
+
+/// 两边的值无法合并时（标量不同或类型不同）保留哪一边
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScalarMerge {
+    KeepLeft,
+    KeepRight,
+    /// 存在冲突时合并失败，不做任何修改
+    Error,
+}
+
+/// 深度合并的策略，Map总是按key递归合并
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MergeStrategy {
+    pub lists: ListMerge,
+    pub scalars: ScalarMerge,
+}
+
+impl MergeStrategy {
+    pub fn new(lists: ListMerge, scalars: ScalarMerge) -> Self {
+        MergeStrategy { lists, scalars }
+    }
+}
+
+impl Default for MergeStrategy {
+    fn default() -> Self {
+        MergeStrategy::new(ListMerge::Replace, ScalarMerge::KeepRight)
+    }
+}
+
+
+/// 将right合并到left上，返回合并后的节点；无法合并的位置追加到conflicts。
+/// 未变化的子树保持原来的Arc，之后可以用diff得出最少的写操作
+pub(crate) fn merge_nodes(
+    focus: &Arc<Focus>,
+    left: &Arc<NodeValue>,
+    right: &Arc<NodeValue>,
+    strategy: &MergeStrategy,
+    conflicts: &mut Vec<Arc<Focus>>,
+) -> Arc<NodeValue> {
+    if Arc::ptr_eq(left, right) {
+        return left.clone();
+    }
+
+    match (left.as_ref(), right.as_ref()) {
+        (NodeValue::Map(left_map), NodeValue::Map(right_map)) => {
+            merge_maps(focus, left, left_map, right_map, strategy, conflicts)
+        },
+        (NodeValue::List(left_list), NodeValue::List(right_list)) => {
+            merge_lists(focus, left, left_list, right_list, strategy, conflicts)
+        },
+        _ => {
+            if left.deep_eq(right) {
+                return left.clone();
+            }
+
+            conflicts.push(focus.clone());
+            match strategy.scalars {
+                ScalarMerge::KeepLeft => left.clone(),
+                ScalarMerge::KeepRight | ScalarMerge::Error => right.clone(),
+            }
+        },
+    }
+}
+
+fn merge_maps(
+    focus: &Arc<Focus>,
+    left: &Arc<NodeValue>,
+    left_map: &MapValue,
+    right_map: &MapValue,
+    strategy: &MergeStrategy,
+    conflicts: &mut Vec<Arc<Focus>>,
+) -> Arc<NodeValue> {
+    let mut keys = right_map.map.keys().cloned().collect::<Vec<String>>();
+    keys.sort();
+
+    let mut merged_map = left_map.clone();
+    let mut changed = false;
+
+    for key in keys {
+        let right_item = right_map.get_item(&key).unwrap();
+
+        let merged_item = match left_map.get_item(&key) {
+            Some(left_item) => {
+                let item_focus = focus.focus(key.as_str());
+                let merged_item = merge_nodes(&item_focus, left_item, right_item, strategy, conflicts);
+                if Arc::ptr_eq(&merged_item, left_item) {
+                    continue;
+                }
+                merged_item
+            },
+            None => right_item.clone(),
+        };
+
+        merged_map = merged_map.set_item(key, merged_item).0;
+        changed = true;
+    }
+
+    if changed {
+        Arc::new(NodeValue::Map(merged_map))
+    } else {
+        left.clone()
+    }
+}
+
+fn merge_lists(
+    focus: &Arc<Focus>,
+    left: &Arc<NodeValue>,
+    left_list: &ListValue,
+    right_list: &ListValue,
+    strategy: &MergeStrategy,
+    conflicts: &mut Vec<Arc<Focus>>,
+) -> Arc<NodeValue> {
+    match strategy.lists {
+        ListMerge::Replace => return Arc::new(NodeValue::List(right_list.clone())),
+        ListMerge::Append if right_list.is_empty() => return left.clone(),
+        _ => {},
+    }
+
+    let mut merged_list = left_list.clone();
+    let mut changed = false;
+
+    for index in 0..right_list.len() {
+        let right_item = right_list.get_item(index).unwrap();
+
+        match (strategy.lists, left_list.get_item(index)) {
+            (ListMerge::ByIndex, Some(left_item)) => {
+                let item_focus = focus.focus(index);
+                let merged_item = merge_nodes(&item_focus, left_item, right_item, strategy, conflicts);
+                if !Arc::ptr_eq(&merged_item, left_item) {
+                    merged_list = merged_list.set_item(index, merged_item).0;
+                    changed = true;
+                }
+            },
+            _ => {
+                merged_list = merged_list.push(right_item.clone());
+                changed = true;
+            },
+        }
+    }
+
+    if changed {
+        Arc::new(NodeValue::List(merged_list))
+    } else {
+        left.clone()
+    }
+}
+
+
+
+/// 三方合并中两边修改了同一位置时的处理：参数为位置、ours和theirs，
+/// 不存在的一边为None；返回None时删除该位置
+pub(crate) type Resolve<'a> = dyn FnMut(
+    &Arc<Focus>, 
+    Option<&Arc<NodeValue>>, 
+    Option<&Arc<NodeValue>>,
+) -> Option<Arc<NodeValue>> + 'a;
+
+/// 以base为共同祖先合并ours和theirs，两边各自的修改都保留。
+/// 两边修改了同一位置且结果不同时，由resolve决定结果；返回None时该位置被删除
+pub(crate) fn merge_three_way(
+    focus: &Arc<Focus>,
+    base: &Arc<NodeValue>,
+    ours: &Arc<NodeValue>,
+    theirs: &Arc<NodeValue>,
+    resolve: &mut Resolve<'_>,
+) -> Option<Arc<NodeValue>> {
+    if Arc::ptr_eq(ours, base) || Arc::ptr_eq(ours, theirs) {
+        return Some(theirs.clone());
+    }
+    if Arc::ptr_eq(theirs, base) {
+        return Some(ours.clone());
+    }
+
+    match (base.as_ref(), ours.as_ref(), theirs.as_ref()) {
+        (NodeValue::Map(base_map), NodeValue::Map(our_map), NodeValue::Map(their_map)) => {
+            Some(merge_maps_three_way(focus, base_map, our_map, their_map, resolve))
+        },
+        (NodeValue::List(base_list), NodeValue::List(our_list), NodeValue::List(their_list))
+            if base_list.len() == our_list.len() && base_list.len() == their_list.len() => {
+            Some(merge_lists_three_way(focus, base_list, our_list, their_list, resolve))
+        },
+        _ => {
+            if ours.deep_eq(theirs) {
+                return Some(theirs.clone());
+            }
+            resolve(focus, Some(ours), Some(theirs))
+        },
+    }
+}
+
+fn merge_maps_three_way(
+    focus: &Arc<Focus>,
+    base_map: &MapValue,
+    our_map: &MapValue,
+    their_map: &MapValue,
+    resolve: &mut Resolve<'_>,
+) -> Arc<NodeValue> {
+    let mut keys = base_map.map.keys()
+        .chain(our_map.map.keys())
+        .chain(their_map.map.keys())
+        .cloned()
+        .collect::<Vec<String>>();
+    keys.sort();
+    keys.dedup();
+
+    let mut merged_map = our_map.clone();
+
+    for key in keys {
+        let base_item = base_map.get_item(&key);
+        let our_item = our_map.get_item(&key);
+        let their_item = their_map.get_item(&key);
+
+        if same_item(their_item, base_item) || same_item(their_item, our_item) {
+            continue;
+        }
+
+        let merged_item = if same_item(our_item, base_item) {
+            their_item.cloned()
+        } else {
+            let item_focus = focus.focus(key.as_str());
+            match (base_item, our_item, their_item) {
+                (Some(base_item), Some(our_item), Some(their_item)) => {
+                    merge_three_way(&item_focus, base_item, our_item, their_item, resolve)
+                },
+                (None, Some(our_item), Some(their_item)) => {
+                    let empty = Arc::new(NodeValue::None);
+                    merge_three_way(&item_focus, &empty, our_item, their_item, resolve)
+                },
+                // 一边删除而另一边修改
+                _ => resolve(&item_focus, our_item, their_item),
+            }
+        };
+
+        merged_map = match merged_item {
+            Some(item) => merged_map.set_item(key, item).0,
+            None => merged_map.remove(&key),
+        };
+    }
+
+    Arc::new(NodeValue::Map(merged_map))
+}
+
+fn merge_lists_three_way(
+    focus: &Arc<Focus>,
+    base_list: &ListValue,
+    our_list: &ListValue,
+    their_list: &ListValue,
+    resolve: &mut Resolve<'_>,
+) -> Arc<NodeValue> {
+    let mut merged_list = our_list.clone();
+    let mut removed = Vec::new();
+
+    for index in 0..base_list.len() {
+        let base_item = base_list.get_item(index).unwrap();
+        let our_item = our_list.get_item(index).unwrap();
+        let their_item = their_list.get_item(index).unwrap();
+
+        if Arc::ptr_eq(their_item, base_item) || Arc::ptr_eq(their_item, our_item) {
+            continue;
+        }
+
+        let item_focus = focus.focus(index);
+        match merge_three_way(&item_focus, base_item, our_item, their_item, resolve) {
+            Some(merged_item) => merged_list = merged_list.set_item(index, merged_item).0,
+            None => removed.push(index),
+        }
+    }
+
+    // 从后往前删除，前面的下标不受影响
+    for index in removed.into_iter().rev() {
+        merged_list = merged_list.remove(index);
+    }
+
+    Arc::new(NodeValue::List(merged_list))
+}
+
+fn same_item(a: Option<&Arc<NodeValue>>, b: Option<&Arc<NodeValue>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
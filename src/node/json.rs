@@ -0,0 +1,162 @@
+use std::fmt;
+use std::sync::Arc;
+
+use serde::ser::{Serialize, Serializer, SerializeMap, SerializeSeq};
+use serde::de::{Deserialize, Deserializer, Visitor, SeqAccess, MapAccess, Error};
+
+use super::value::NodeValue;
+use super::list::ListValue;
+use super::map::MapValue;
+
+
+impl Serialize for NodeValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            NodeValue::None => serializer.serialize_unit(),
+            NodeValue::Bool(v) => serializer.serialize_bool(*v),
+            NodeValue::Integer(v) => serializer.serialize_i64(*v),
+            NodeValue::Float(v) => serializer.serialize_f64(*v),
+            NodeValue::String(v) => serializer.serialize_str(v),
+            NodeValue::List(v) => v.serialize(serializer),
+            NodeValue::Map(v) => v.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for ListValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.list.len()))?;
+        for item in self.list.iter() {
+            seq.serialize_element(item.as_ref())?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for MapValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // im::HashMap的遍历顺序不确定，按key排序以保证输出稳定
+        let mut entries = self.map.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (key, item) in entries {
+            map.serialize_entry(key, item.as_ref())?;
+        }
+        map.end()
+    }
+}
+
+
+struct NodeValueVisitor;
+
+impl<'de> Visitor<'de> for NodeValueVisitor {
+    type Value = NodeValue;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("any valid JSON value")
+    }
+
+    fn visit_unit<E: Error>(self) -> Result<NodeValue, E> {
+        Ok(NodeValue::None)
+    }
+
+    fn visit_none<E: Error>(self) -> Result<NodeValue, E> {
+        Ok(NodeValue::None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<NodeValue, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_bool<E: Error>(self, value: bool) -> Result<NodeValue, E> {
+        Ok(NodeValue::Bool(value))
+    }
+
+    fn visit_i64<E: Error>(self, value: i64) -> Result<NodeValue, E> {
+        Ok(NodeValue::Integer(value))
+    }
+
+    fn visit_u64<E: Error>(self, value: u64) -> Result<NodeValue, E> {
+        if value > i64::MAX as u64 {
+            return Err(E::custom(format!("integer {} is out of the range of i64", value)));
+        }
+        Ok(NodeValue::Integer(value as i64))
+    }
+
+    fn visit_f64<E: Error>(self, value: f64) -> Result<NodeValue, E> {
+        Ok(NodeValue::Float(value))
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<NodeValue, E> {
+        Ok(NodeValue::String(value.to_string()))
+    }
+
+    fn visit_string<E: Error>(self, value: String) -> Result<NodeValue, E> {
+        Ok(NodeValue::String(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<NodeValue, A::Error> {
+        Ok(NodeValue::List(ListValueVisitor.visit_seq(seq)?))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<NodeValue, A::Error> {
+        Ok(NodeValue::Map(MapValueVisitor.visit_map(map)?))
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<NodeValue, D::Error> {
+        deserializer.deserialize_any(NodeValueVisitor)
+    }
+}
+
+
+struct ListValueVisitor;
+
+impl<'de> Visitor<'de> for ListValueVisitor {
+    type Value = ListValue;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("a JSON array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ListValue, A::Error> {
+        let mut list_value = ListValue::new();
+        while let Some(item) = seq.next_element::<NodeValue>()? {
+            list_value.list.push_back(Arc::new(item));
+        }
+        Ok(list_value)
+    }
+}
+
+impl<'de> Deserialize<'de> for ListValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ListValue, D::Error> {
+        deserializer.deserialize_seq(ListValueVisitor)
+    }
+}
+
+
+struct MapValueVisitor;
+
+impl<'de> Visitor<'de> for MapValueVisitor {
+    type Value = MapValue;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("a JSON object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<MapValue, A::Error> {
+        let mut map_value = MapValue::new();
+        while let Some((key, item)) = map.next_entry::<String, NodeValue>()? {
+            map_value.map.insert(key, Arc::new(item));
+        }
+        Ok(map_value)
+    }
+}
+
+impl<'de> Deserialize<'de> for MapValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MapValue, D::Error> {
+        deserializer.deserialize_map(MapValueVisitor)
+    }
+}
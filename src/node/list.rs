@@ -21,6 +21,42 @@ impl ListValue {
         self.list.len() as isize
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// 将负数下标换算为从头开始的下标，-1为最后一个
+    #[inline]
+    pub fn absolute_index(&self, index: CircularZeroIndex) -> CircularZeroIndex {
+        if index >= 0 {
+            index
+        } else {
+            self.list.len() as isize + index
+        }
+    }
+
+    /// item这个节点（按指针）在列表中第nth次出现的下标，nth从0开始。
+    /// 同一个节点可以在列表中出现多次
+    pub(crate) fn position(&self, item: &Arc<NodeValue>, nth: usize) -> Option<CircularZeroIndex> {
+        self.list.iter()
+            .enumerate()
+            .filter(|(_, current)| Arc::ptr_eq(current, item))
+            .nth(nth)
+            .map(|(index, _)| index as isize)
+    }
+
+    /// 下标处的节点，及它是该节点（按指针）在列表中第几次出现
+    pub(crate) fn occurrence(&self, index: CircularZeroIndex) -> Option<(&Arc<NodeValue>, usize)> {
+        let index = self.absolute_index(index);
+        let item = self.get_item(index)?;
+        let nth = self.list.iter()
+            .take(index as usize)
+            .filter(|current| Arc::ptr_eq(current, item))
+            .count();
+        Some((item, nth))
+    }
+
     pub fn get_item(&self, index: CircularZeroIndex) -> Option<&Arc<NodeValue>> {
         let index = if index >= 0 {
             index
@@ -215,6 +251,12 @@ impl ListValue {
 //     }
 // }
 
+impl Default for ListValue {
+    fn default() -> Self {
+        ListValue::new()
+    }
+}
+
 impl Clone for ListValue {
     fn clone(&self) -> Self {
         ListValue {
@@ -54,6 +54,11 @@ impl MapValue {
         self.map.len()
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
     #[inline]
     pub fn contains_key(&self, key: &String) -> bool {
         self.map.contains_key(key)
@@ -62,6 +67,12 @@ impl MapValue {
 }
 
 
+impl Default for MapValue {
+    fn default() -> Self {
+        MapValue::new()
+    }
+}
+
 impl Clone for MapValue {
     fn clone(&self) -> Self {
         MapValue {
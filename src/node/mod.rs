@@ -5,7 +5,10 @@ mod map;
 mod list;
 
 mod conversion;
+mod json;
+mod structural;
 
 pub use value::NodeValue;
 pub use list::ListValue;
 pub use map::MapValue;
+pub use structural::StructuralKey;
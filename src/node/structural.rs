@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use super::value::NodeValue;
+use super::list::ListValue;
+use super::map::MapValue;
+
+// NodeValue的PartialEq/Hash按指针判等，供ChangeLogger按节点身份索引。
+// 这里提供按内容比较的接口，两者互不影响。
+
+impl NodeValue {
+    /// 按内容比较两棵子树。Float按数值比较，但NaN与NaN相等，0.0与-0.0相等
+    pub fn deep_eq(&self, other: &NodeValue) -> bool {
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+
+        match (self, other) {
+            (NodeValue::None, NodeValue::None) => true,
+            (NodeValue::Bool(a), NodeValue::Bool(b)) => a == b,
+            (NodeValue::Integer(a), NodeValue::Integer(b)) => a == b,
+            (NodeValue::Float(a), NodeValue::Float(b)) => float_bits(*a) == float_bits(*b),
+            (NodeValue::String(a), NodeValue::String(b)) => a == b,
+            (NodeValue::List(a), NodeValue::List(b)) => a.deep_eq(b),
+            (NodeValue::Map(a), NodeValue::Map(b)) => a.deep_eq(b),
+            _ => false,
+        }
+    }
+
+    /// 子树内容的摘要，与进程、HashMap的遍历顺序无关，内容相同则摘要相同
+    pub fn content_digest(&self) -> u64 {
+        let mut hasher = DigestHasher::new();
+        self.write_digest(&mut hasher);
+        hasher.finish()
+    }
+
+    fn write_digest(&self, hasher: &mut DigestHasher) {
+        match self {
+            NodeValue::None => hasher.write_u8(0),
+            NodeValue::Bool(v) => {
+                hasher.write_u8(1);
+                hasher.write_u8(*v as u8);
+            },
+            NodeValue::Integer(v) => {
+                hasher.write_u8(2);
+                hasher.write_u64(*v as u64);
+            },
+            NodeValue::Float(v) => {
+                hasher.write_u8(3);
+                hasher.write_u64(float_bits(*v));
+            },
+            NodeValue::String(v) => {
+                hasher.write_u8(4);
+                hasher.write_str(v);
+            },
+            NodeValue::List(list_value) => {
+                hasher.write_u8(5);
+                hasher.write_u64(list_value.list.len() as u64);
+                for item in list_value.list.iter() {
+                    hasher.write_u64(item.content_digest());
+                }
+            },
+            NodeValue::Map(map_value) => {
+                hasher.write_u8(6);
+                hasher.write_u64(map_value.map.len() as u64);
+
+                let mut entries = map_value.map.iter().collect::<Vec<_>>();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                for (key, item) in entries {
+                    hasher.write_str(key);
+                    hasher.write_u64(item.content_digest());
+                }
+            },
+        }
+    }
+}
+
+impl ListValue {
+    pub fn deep_eq(&self, other: &ListValue) -> bool {
+        if self.list.ptr_eq(&other.list) {
+            return true;
+        }
+
+        self.list.len() == other.list.len()
+            && self.list.iter().zip(other.list.iter())
+                .all(|(a, b)| Arc::ptr_eq(a, b) || a.deep_eq(b))
+    }
+}
+
+impl MapValue {
+    pub fn deep_eq(&self, other: &MapValue) -> bool {
+        if self.map.ptr_eq(&other.map) {
+            return true;
+        }
+
+        self.map.len() == other.map.len()
+            && self.map.iter().all(|(key, a)| {
+                match other.map.get(key) {
+                    Some(b) => Arc::ptr_eq(a, b) || a.deep_eq(b),
+                    None => false,
+                }
+            })
+    }
+}
+
+/// -0.0归一为0.0，所有NaN归一为同一个NaN
+#[inline]
+fn float_bits(value: f64) -> u64 {
+    if value.is_nan() {
+        f64::NAN.to_bits()
+    } else if value == 0.0 {
+        0
+    } else {
+        value.to_bits()
+    }
+}
+
+
+/// 按内容判等和哈希的NodeValue包装，可以作为HashMap/HashSet的键
+#[derive(Clone)]
+pub struct StructuralKey {
+    node: Arc<NodeValue>,
+    digest: u64,
+}
+
+impl StructuralKey {
+    pub fn new(node: Arc<NodeValue>) -> StructuralKey {
+        let digest = node.content_digest();
+        StructuralKey { node, digest }
+    }
+
+    #[inline]
+    pub fn node(&self) -> &Arc<NodeValue> {
+        &self.node
+    }
+
+    #[inline]
+    pub fn digest(&self) -> u64 {
+        self.digest
+    }
+}
+
+impl From<Arc<NodeValue>> for StructuralKey {
+    #[inline]
+    fn from(node: Arc<NodeValue>) -> StructuralKey {
+        StructuralKey::new(node)
+    }
+}
+
+impl core::cmp::PartialEq for StructuralKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.digest == other.digest && self.node.deep_eq(&other.node)
+    }
+}
+
+impl core::cmp::Eq for StructuralKey {}
+
+impl std::hash::Hash for StructuralKey {
+    fn hash<H: std::hash::Hasher>(&self, into: &mut H) {
+        into.write_u64(self.digest)
+    }
+}
+
+impl ::std::fmt::Debug for StructuralKey {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        fmt.write_fmt(format_args!("<StructuralKey {:016x} ", self.digest))?;
+        self.node.fmt(fmt)?;
+        fmt.write_str(">")
+    }
+}
+
+
+/// 64位FNV-1a，结果只取决于写入的字节，不随进程变化
+struct DigestHasher {
+    state: u64,
+}
+
+impl DigestHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> DigestHasher {
+        DigestHasher { state: Self::OFFSET_BASIS }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= u64::from(*byte);
+            self.state = self.state.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, value: u8) {
+        self.write(&[value]);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_u64(value.len() as u64);
+        self.write(value.as_bytes());
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.state
+    }
+}
@@ -12,6 +12,21 @@ pub enum NodeValue {
     Map(MapValue),
 }
 
+impl NodeValue {
+    /// 值类型的名称，用于错误信息
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            NodeValue::None => "none",
+            NodeValue::Bool(_) => "bool",
+            NodeValue::Integer(_) => "integer",
+            NodeValue::Float(_) => "float",
+            NodeValue::String(_) => "string",
+            NodeValue::List(_) => "list",
+            NodeValue::Map(_) => "map",
+        }
+    }
+}
+
 
 impl std::fmt::Debug for NodeValue {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
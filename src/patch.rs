@@ -0,0 +1,378 @@
+// RFC 6902 JSON Patch
+use std::sync::Arc;
+
+use crate::error::Error;
+use crate::focus::{AccessKey, Focus, FocusLocator, CircularZeroIndex};
+use crate::node::{NodeValue, MapValue};
+use crate::domain::{get_item_node, NodeEvent};
+use crate::diff::DiffOp;
+
+
+pub(crate) enum PatchOp {
+    Add { path: Vec<String>, value: Arc<NodeValue> },
+    Remove { path: Vec<String> },
+    Replace { path: Vec<String>, value: Arc<NodeValue> },
+    Move { from: Vec<String>, path: Vec<String> },
+    Copy { from: Vec<String>, path: Vec<String> },
+    Test { path: Vec<String>, value: Arc<NodeValue> },
+}
+
+/// 补丁展开后的写操作，focus为被写入的项，下标已经解析为具体位置
+pub(crate) enum PatchWrite {
+    Set { focus: Arc<Focus>, value: Arc<NodeValue> },
+    Insert { focus: Arc<Focus>, value: Arc<NodeValue> },
+    Remove { focus: Arc<Focus> },
+}
+
+impl From<DiffOp> for PatchWrite {
+    fn from(op: DiffOp) -> Self {
+        match op {
+            DiffOp::Added { focus, value } => PatchWrite::Set { focus, value },
+            DiffOp::Replaced { focus, new_value, .. } => PatchWrite::Set { focus, value: new_value },
+            DiffOp::Removed { focus, .. } => PatchWrite::Remove { focus },
+            DiffOp::ListInserted { focus, value } => PatchWrite::Insert { focus, value },
+            DiffOp::ListDeleted { focus, .. } => PatchWrite::Remove { focus },
+        }
+    }
+}
+
+
+/// 解析补丁文档，应为由操作对象组成的数组
+pub(crate) fn parse_patch(patch: &NodeValue) -> Result<Vec<PatchOp>, Error> {
+    let list_value = match patch {
+        NodeValue::List(list_value) => list_value,
+        _ => return invalid_patch("the patch should be an array of operations".to_string()),
+    };
+
+    let mut ops = Vec::new();
+    for (idx, item) in list_value.list.iter().enumerate() {
+        let op_map = match item.as_ref() {
+            NodeValue::Map(map_value) => map_value,
+            _ => return invalid_patch(format!("operation #{} should be an object", idx)),
+        };
+
+        let op_name = get_string_member(op_map, "op", idx)?;
+        let path = parse_pointer(&get_string_member(op_map, "path", idx)?, idx)?;
+
+        let op = match op_name.as_str() {
+            "add" => PatchOp::Add { path, value: get_value_member(op_map, idx)? },
+            "remove" => PatchOp::Remove { path },
+            "replace" => PatchOp::Replace { path, value: get_value_member(op_map, idx)? },
+            "move" => {
+                let from = parse_pointer(&get_string_member(op_map, "from", idx)?, idx)?;
+                if from.len() < path.len() && path.starts_with(&from) {
+                    return invalid_patch(format!(
+                        "operation #{} moves a location into one of its children", idx));
+                }
+                PatchOp::Move { from, path }
+            },
+            "copy" => {
+                let from = parse_pointer(&get_string_member(op_map, "from", idx)?, idx)?;
+                PatchOp::Copy { from, path }
+            },
+            "test" => PatchOp::Test { path, value: get_value_member(op_map, idx)? },
+            _ => return invalid_patch(format!("unknown op '{}' in operation #{}", op_name, idx)),
+        };
+        ops.push(op);
+    }
+
+    Ok(ops)
+}
+
+fn get_string_member(op_map: &MapValue, name: &str, idx: usize) -> Result<String, Error> {
+    match op_map.get_item(&name.to_string()).map(|v| v.as_ref()) {
+        Some(NodeValue::String(value)) => Ok(value.to_string()),
+        _ => invalid_patch(format!("operation #{} requires a string member '{}'", idx, name)),
+    }
+}
+
+fn get_value_member(op_map: &MapValue, idx: usize) -> Result<Arc<NodeValue>, Error> {
+    match op_map.get_item(&"value".to_string()) {
+        Some(value) => Ok(value.clone()),
+        None => invalid_patch(format!("operation #{} requires a member 'value'", idx)),
+    }
+}
+
+/// 按RFC 6901解析JSON Pointer，空串指向文档自身
+fn parse_pointer(pointer: &str, idx: usize) -> Result<Vec<String>, Error> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if !pointer.starts_with('/') {
+        return invalid_patch(format!(
+            "the path '{}' of operation #{} should start with '/'", pointer, idx));
+    }
+
+    Ok(pointer[1..]
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+#[inline]
+fn invalid_patch<T>(message: String) -> Result<T, Error> {
+    Err(Error::InvalidPatch(message))
+}
+
+
+/// 在document上依次试运行全部操作，成功则返回展开后的写操作，
+/// 任何一步失败都不会产生写操作
+pub(crate) fn plan_patch(
+    focus: &Arc<Focus>,
+    document: &Arc<NodeValue>,
+    ops: &[PatchOp],
+) -> Result<Vec<PatchWrite>, Error> {
+    let mut writes = Vec::new();
+    let mut document = document.clone();
+
+    for op in ops {
+        document = match op {
+            PatchOp::Add { path, value } => {
+                add_value(focus, &document, path, value.clone(), &mut writes)?
+            },
+            PatchOp::Remove { path } => {
+                remove_value(focus, &document, path, &mut writes)?
+            },
+            PatchOp::Replace { path, value } => {
+                replace_value(focus, &document, path, value.clone(), &mut writes)?
+            },
+            PatchOp::Move { from, path } => {
+                let value = get_value(focus, &document, from)?;
+                let document = remove_value(focus, &document, from, &mut writes)?;
+                add_value(focus, &document, path, value, &mut writes)?
+            },
+            PatchOp::Copy { from, path } => {
+                let value = get_value(focus, &document, from)?;
+                add_value(focus, &document, path, value, &mut writes)?
+            },
+            PatchOp::Test { path, value } => {
+                let current = get_value(focus, &document, path)?;
+                if !current.deep_eq(value) {
+                    return Err(Error::PatchTestFailed {
+                        focus: pointer_focus(focus, &document, path)?,
+                    });
+                }
+                document
+            },
+        };
+    }
+
+    Ok(writes)
+}
+
+/// 按父节点的类型解释路径中的一段：Map中为key，List中为下标，'-'表示末尾之后
+fn resolve_key(
+    parent_focus: &Arc<Focus>,
+    parent: &NodeValue,
+    token: &str,
+    allow_append: bool,
+) -> Result<AccessKey, Error> {
+    match parent {
+        NodeValue::Map(_) => Ok(AccessKey::Key(token.to_string())),
+        NodeValue::List(list_value) => {
+            if token == "-" && allow_append {
+                return Ok(AccessKey::Index(list_value.len()));
+            }
+
+            let is_index = !token.is_empty()
+                && token.bytes().all(|b| b.is_ascii_digit())
+                && (token == "0" || !token.starts_with('0'));
+
+            match token.parse::<CircularZeroIndex>() {
+                Ok(index) if is_index => Ok(AccessKey::Index(index)),
+                _ => Error::mismatched_access_key(parent_focus, &AccessKey::Key(token.to_string())),
+            }
+        },
+        _ => Error::should_be_collection(parent_focus),
+    }
+}
+
+fn get_value(
+    focus: &Arc<Focus>,
+    node: &Arc<NodeValue>,
+    path: &[String],
+) -> Result<Arc<NodeValue>, Error> {
+    match path.split_first() {
+        None => Ok(node.clone()),
+        Some((token, rest)) => {
+            let key = resolve_key(focus, node, token, false)?;
+            let item = get_item_node(focus, node, &key)?;
+            get_value(&focus.focus(key), &item, rest)
+        }
+    }
+}
+
+fn pointer_focus(
+    focus: &Arc<Focus>,
+    node: &Arc<NodeValue>,
+    path: &[String],
+) -> Result<Arc<Focus>, Error> {
+    match path.split_first() {
+        None => Ok(focus.clone()),
+        Some((token, rest)) => {
+            let key = resolve_key(focus, node, token, false)?;
+            let item = get_item_node(focus, node, &key)?;
+            pointer_focus(&focus.focus(key), &item, rest)
+        }
+    }
+}
+
+/// 沿path找到最后一段的父节点，以func修改后逐层向上替换，返回新的node
+fn modify_parent<F>(
+    focus: &Arc<Focus>,
+    node: &Arc<NodeValue>,
+    path: &[String],
+    func: F,
+) -> Result<Arc<NodeValue>, Error>
+where
+    F: FnOnce(&Arc<Focus>, &Arc<NodeValue>, &str) -> Result<Arc<NodeValue>, Error>,
+{
+    match path.split_first() {
+        None => unreachable!("the path should not be empty"),
+        Some((token, [])) => func(focus, node, token),
+        Some((token, rest)) => {
+            let key = resolve_key(focus, node, token, false)?;
+            let item = get_item_node(focus, node, &key)?;
+            let new_item = modify_parent(&focus.focus(key.clone()), &item, rest, func)?;
+            Ok(set_item(node, &key, new_item))
+        }
+    }
+}
+
+fn set_item(node: &Arc<NodeValue>, key: &AccessKey, item: Arc<NodeValue>) -> Arc<NodeValue> {
+    match (node.as_ref(), key) {
+        (NodeValue::Map(map_value), AccessKey::Key(key)) => {
+            Arc::new(NodeValue::Map(map_value.set_item(key.to_string(), item).0))
+        },
+        (NodeValue::List(list_value), AccessKey::Index(index)) => {
+            Arc::new(NodeValue::List(list_value.set_item(*index, item).0))
+        },
+        _ => unreachable!("the key has been resolved against the node"),
+    }
+}
+
+fn add_value(
+    focus: &Arc<Focus>,
+    document: &Arc<NodeValue>,
+    path: &[String],
+    value: Arc<NodeValue>,
+    writes: &mut Vec<PatchWrite>,
+) -> Result<Arc<NodeValue>, Error> {
+    if path.is_empty() {
+        writes.push(PatchWrite::Set { focus: focus.clone(), value: value.clone() });
+        return Ok(value);
+    }
+
+    modify_parent(focus, document, path, |parent_focus, parent, token| {
+        let key = resolve_key(parent_focus, parent, token, true)?;
+        let item_focus = parent_focus.focus(key.clone());
+
+        match (parent.as_ref(), key) {
+            (NodeValue::Map(map_value), AccessKey::Key(key)) => {
+                writes.push(PatchWrite::Set { focus: item_focus, value: value.clone() });
+                Ok(Arc::new(NodeValue::Map(map_value.set_item(key, value).0)))
+            },
+            (NodeValue::List(list_value), AccessKey::Index(index)) => {
+                if index > list_value.len() {
+                    return Error::no_such_item(parent_focus, &AccessKey::Index(index));
+                }
+                writes.push(PatchWrite::Insert { focus: item_focus, value: value.clone() });
+                Ok(Arc::new(NodeValue::List(list_value.insert(index, value))))
+            },
+            (_, key) => Error::mismatched_access_key(parent_focus, &key),
+        }
+    })
+}
+
+fn remove_value(
+    focus: &Arc<Focus>,
+    document: &Arc<NodeValue>,
+    path: &[String],
+    writes: &mut Vec<PatchWrite>,
+) -> Result<Arc<NodeValue>, Error> {
+    if path.is_empty() {
+        return invalid_patch("the target document itself cannot be removed".to_string());
+    }
+
+    modify_parent(focus, document, path, |parent_focus, parent, token| {
+        let key = resolve_key(parent_focus, parent, token, false)?;
+        get_item_node(parent_focus, parent, &key)?;
+
+        writes.push(PatchWrite::Remove { focus: parent_focus.focus(key.clone()) });
+
+        match (parent.as_ref(), key) {
+            (NodeValue::Map(map_value), AccessKey::Key(key)) => {
+                Ok(Arc::new(NodeValue::Map(map_value.remove(&key))))
+            },
+            (NodeValue::List(list_value), AccessKey::Index(index)) => {
+                Ok(Arc::new(NodeValue::List(list_value.remove(index))))
+            },
+            (_, key) => Error::mismatched_access_key(parent_focus, &key),
+        }
+    })
+}
+
+fn replace_value(
+    focus: &Arc<Focus>,
+    document: &Arc<NodeValue>,
+    path: &[String],
+    value: Arc<NodeValue>,
+    writes: &mut Vec<PatchWrite>,
+) -> Result<Arc<NodeValue>, Error> {
+    if path.is_empty() {
+        writes.push(PatchWrite::Set { focus: focus.clone(), value: value.clone() });
+        return Ok(value);
+    }
+
+    modify_parent(focus, document, path, |parent_focus, parent, token| {
+        let key = resolve_key(parent_focus, parent, token, false)?;
+        get_item_node(parent_focus, parent, &key)?;
+
+        writes.push(PatchWrite::Set { focus: parent_focus.focus(key.clone()), value: value.clone() });
+        Ok(set_item(parent, &key, value))
+    })
+}
+
+
+/// 将一条变更事件转换为JSON Patch操作，内部节点的事件返回None
+pub(crate) fn patch_operation(event: &NodeEvent) -> Option<NodeValue> {
+    use NodeEvent::*;
+
+    let (op, focus, value) = match event {
+        RootUpdated { focus, value, .. } => ("replace", focus, Some(value)),
+        ValueCreated { focus, value, .. } => ("add", focus, Some(value)),
+        ValueUpdated { focus, value, .. } => ("replace", focus, Some(value)),
+        ValueDeleted { focus, .. } => ("remove", focus, None),
+        ListItemInserted { focus, value, .. } => ("add", focus, Some(value)),
+        ListItemDeleted { focus, .. } => ("remove", focus, None),
+        InternalNodeUpdated { .. } => return None,
+        InternalLineUpdated { .. } => return None,
+        InternalRootUpdated { .. } => return None,
+        // 被拒绝的写入没有记录修改；结果与后到的写入相同时，导出它的修改就已得到结果
+        Conflict { rejected: true, .. } => return None,
+        Conflict { ours, value, .. } if same_value(ours.as_ref(), value.as_ref()) => return None,
+        // 否则冲突的结果覆盖之前记录的写入
+        Conflict { focus, value: Some(value), .. } => ("replace", focus, Some(value)),
+        Conflict { focus, value: None, .. } => ("remove", focus, None),
+    };
+
+    let op_map = MapValue::new()
+        .set_item("op".to_string(), Arc::new(NodeValue::from(op))).0
+        .set_item("path".to_string(), Arc::new(NodeValue::from(focus.json_pointer()))).0;
+
+    let op_map = match value {
+        Some(value) => op_map.set_item("value".to_string(), value.clone()).0,
+        None => op_map,
+    };
+
+    Some(NodeValue::Map(op_map))
+}
+
+fn same_value(a: Option<&Arc<NodeValue>>, b: Option<&Arc<NodeValue>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b) || a.deep_eq(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
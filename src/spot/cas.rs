@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use super::spot::Spot;
+use crate::error::Error;
+use crate::node::NodeValue;
+
+
+impl Spot {
+    /// focus处的节点仍是expected这个Arc时写入value；
+    /// 否则不做修改，返回带有当前值的`Error::Conflict`
+    pub fn compare_and_set<V: Into<NodeValue>>(
+        self,
+        expected: &Arc<NodeValue>,
+        value: V,
+    ) -> Result<Spot, Error> {
+        let new_value = Arc::new(value.into());
+
+        let cone = self.cone.clone();
+        let _writer = cone.lock_writer();
+
+        // 以最新的父节点写入，其间兄弟节点的修改不受影响。
+        // 该位置已被删除时当前值为None
+        let current = match Spot::locate(&cone, &self.focus) {
+            Ok(current) => current,
+            Err(Error::NoSuchItem { .. }) | Err(Error::WrongItemAccess { .. }) => {
+                return Error::conflict(&self.focus, new_value, Arc::new(NodeValue::None));
+            },
+            Err(err) => return Err(err),
+        };
+        if !Arc::ptr_eq(&current.node, expected) {
+            return Error::conflict(&self.focus, new_value, current.node);
+        }
+
+        current.set_value_node(new_value)
+    }
+
+    /// 取得该spot以来focus处的节点没有被修改过时写入value
+    #[inline]
+    pub fn set_if_unchanged<V: Into<NodeValue>>(self, value: V) -> Result<Spot, Error> {
+        let expected = self.node.clone();
+        self.compare_and_set(&expected, value)
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use super::spot::Spot;
+use crate::error::Error;
+use crate::focus::{AccessKey, FocusLocator};
+use crate::node::NodeValue;
+
+
+impl Spot {
+    /// 子项的访问键：Map按key排序，List按下标顺序
+    pub fn keys(&self) -> Result<impl Iterator<Item = AccessKey>, Error> {
+        let keys = match self.node.as_ref() {
+            NodeValue::Map(map_value) => {
+                let mut keys = map_value.map.keys().cloned().collect::<Vec<String>>();
+                keys.sort();
+                keys.into_iter().map(AccessKey::Key).collect::<Vec<AccessKey>>()
+            },
+            NodeValue::List(list_value) => {
+                (0..list_value.len()).map(AccessKey::Index).collect()
+            },
+            _ => return Error::should_be_collection(&self.focus),
+        };
+        Ok(keys.into_iter())
+    }
+
+    /// 按keys的顺序取得各子项及其访问键
+    pub fn items(&self) -> Result<impl Iterator<Item = (AccessKey, Spot)>, Error> {
+        let keys = self.keys()?;
+
+        let cone = self.cone.clone();
+        let focus = self.focus.clone();
+        let node = self.node.clone();
+
+        Ok(keys.into_iter().map(move |access_key| {
+            let item_node = child_node(&node, &access_key);
+            let item_spot = Spot::new(
+                cone.clone(),
+                focus.focus(access_key.clone()),
+                item_node,
+                Some(node.clone()),
+            );
+            (access_key, item_spot)
+        }))
+    }
+
+    /// 同items，子项及其访问键
+    pub fn children(&self) -> Result<impl Iterator<Item = (AccessKey, Spot)>, Error> {
+        self.items()
+    }
+}
+
+#[inline]
+fn child_node(parent: &Arc<NodeValue>, access_key: &AccessKey) -> Arc<NodeValue> {
+    let item_node = match (parent.as_ref(), access_key) {
+        (NodeValue::Map(map_value), AccessKey::Key(key)) => map_value.get_item(key),
+        (NodeValue::List(list_value), AccessKey::Index(index)) => list_value.get_item(*index),
+        _ => None,
+    };
+    item_node.expect("the key should come from the same collection").clone()
+}
@@ -0,0 +1,12 @@
+use super::spot::Spot;
+use crate::diff::{DiffOp, diff_nodes};
+
+
+impl Spot {
+    /// 从该位置的子树变化到other位置的子树所需的操作，路径相对于self的focus
+    pub fn diff(&self, other: &Spot) -> Vec<DiffOp> {
+        let mut ops = Vec::new();
+        diff_nodes(&self.focus, &self.node, &other.node, &mut ops);
+        ops
+    }
+}
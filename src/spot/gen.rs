@@ -5,7 +5,7 @@ use crate::error::Error;
 use crate::focus::{AccessKey, FocusLocator};
 use crate::node::{ListValue, MapValue, NodeValue};
 
-use crate::domain::Cone;
+use crate::domain::{Cone, get_item_node};
 use crate::focus::Focus;
 
 impl Spot {
@@ -25,7 +25,10 @@ impl Spot {
         self.set_value_node(new_value_node)
     }
 
-    fn set_value_node(self, new_value: Arc<NodeValue>) -> Result<Spot, Error> {
+    pub(crate) fn set_value_node(self, new_value: Arc<NodeValue>) -> Result<Spot, Error> {
+
+        let cone = self.cone.clone();
+        let _writer = cone.lock_writer();
 
         let focus = &self.focus;
         let old_parent = &self.parent;
@@ -38,27 +41,28 @@ impl Spot {
                 new_value.clone(),
             )?;
 
-            Ok(Spot {
-                cone: self.cone,
-                focus: self.focus,
-                parent: Some(new_parent),
-                node: new_value,
-            })
+            Ok(Spot::new(
+                self.cone,
+                self.focus,
+                new_value,
+                Some(new_parent),
+            ))
         } else { // the root node without parent
-            
-            self.cone.remount_root(new_value.clone());
-            self.cone.log_root_updated(
+            cone.check_conflict(&self.focus, Some(&self.node), Some(&new_value))?;
+
+            cone.log_root_updated(
                 self.focus.clone(), 
                 self.node, 
                 new_value.clone()
             );
-
-            Ok(Spot {
-                cone: self.cone,
-                focus: self.focus,
-                parent: None,
-                node: new_value,
-            })
+            cone.remount_root(new_value.clone());
+
+            Ok(Spot::new(
+                self.cone,
+                self.focus,
+                new_value,
+                None,
+            ))
         }
     }
 }
@@ -83,7 +87,7 @@ impl Spot {
         self.set_item_node(access_key.into(), new_item_node)
     }
 
-    fn set_item_node(
+    pub(crate) fn set_item_node(
         self,
         access_key: AccessKey,
         new_item_node: Arc<NodeValue>,
@@ -94,12 +98,12 @@ impl Spot {
 
         let new_parent = set_item_node(&self.cone, &self.node, &item_focus, new_item_node)?;
 
-        Ok(Spot {
-            cone: self.cone,
-            focus: self.focus,
-            node: new_parent,
-            parent: self.parent,
-        })
+        Ok(Spot::new(
+            self.cone,
+            self.focus,
+            new_parent,
+            self.parent,
+        ))
     }
 }
 
@@ -109,7 +113,13 @@ fn set_item_node(
     item_focus: &Arc<Focus>, 
     new_item: Arc<NodeValue>
 ) -> Result<Arc<NodeValue>, Error> {
-    
+
+    let _writer = domain.lock_writer();
+
+    let item_focus = &absolute_item_focus(parent, item_focus);
+    let base_item = get_item_node(item_focus.get_parent().unwrap(), parent, &item_focus.get_access_key()).ok();
+    domain.check_conflict(item_focus, base_item.as_ref(), Some(&new_item))?;
+
     let (new_parent, old_item) = match (parent.as_ref(), item_focus.get_access_key()) {
         (NodeValue::Map(map_value), AccessKey::Key(ref key)) => {
             let (new_map, old_item) = map_value.set_item(key.to_string(), new_item.clone());
@@ -118,6 +128,11 @@ fn set_item_node(
             Ok((new_parent_node, old_item))
         }
         (NodeValue::List(list_value), AccessKey::Index(index)) => {
+            if list_value.get_item(index).is_none() {
+                let parent_focus = item_focus.get_parent().unwrap();
+                return Error::no_such_item(parent_focus, &AccessKey::Index(index));
+            }
+
             let (new_list, old_item) = list_value.set_item(index, new_item.clone());
             let new_parent_node = Arc::new(NodeValue::List(new_list));
             Ok((new_parent_node, old_item))
@@ -144,6 +159,17 @@ fn set_item_node(
     Ok(new_parent)
 }
 
+/// 列表项的focus统一使用从头开始的下标，日志中的路径才与位置一一对应
+pub(crate) fn absolute_item_focus(parent: &Arc<NodeValue>, item_focus: &Arc<Focus>) -> Arc<Focus> {
+    match (parent.as_ref(), item_focus.get_access_key()) {
+        (NodeValue::List(list_value), AccessKey::Index(index)) if index < 0 => {
+            let parent_focus = item_focus.get_parent().unwrap();
+            parent_focus.focus(list_value.absolute_index(index))
+        },
+        _ => item_focus.clone(),
+    }
+}
+
 impl Spot {
     pub fn remove<K: Into<AccessKey>>(self, access_key: K) -> Result<Spot, Error> {
         let collection_focus = self.focus;
@@ -151,7 +177,13 @@ impl Spot {
 
         let access_key = access_key.into();
 
-        let item_focus = collection_focus.focus(access_key.clone());
+        let cone = self.cone.clone();
+        let _writer = cone.lock_writer();
+
+        let item_focus = absolute_item_focus(
+            collection_node, 
+            &collection_focus.focus(access_key.clone())
+        );
 
         let (new_collection, old_value) = match (collection_node.as_ref(), &access_key) {
             (NodeValue::Map(map_value), AccessKey::Key(ref key)) => {
@@ -173,19 +205,31 @@ impl Spot {
             (_, access_key) => Error::mismatched_access_key(&collection_focus, &access_key),
         }?;
 
-        self.cone.log_value_deleted(
-            &item_focus, 
-            collection_node, 
-            old_value, 
-            &new_collection
-        );
+        if let NodeValue::List(_) = collection_node.as_ref() {
+            // 删除列表项改变其后各项的下标，整个列表不应被其他写入修改过
+            cone.check_conflict(&collection_focus, Some(collection_node), Some(&new_collection))?;
+            cone.log_listitem_deleted(
+                &item_focus, 
+                collection_node, 
+                old_value, 
+                &new_collection
+            );
+        } else {
+            cone.check_conflict(&item_focus, Some(old_value), None)?;
+            cone.log_value_deleted(
+                &item_focus, 
+                collection_node, 
+                old_value, 
+                &new_collection
+            );
+        }
 
-        Ok(Spot {
-            cone: self.cone,
-            focus: collection_focus,
-            node: new_collection,
-            parent: self.parent,
-        })
+        Ok(Spot::new(
+            self.cone,
+            collection_focus,
+            new_collection,
+            self.parent,
+        ))
     }
 }
 
@@ -200,4 +244,8 @@ impl Spot {
             _ => Error::should_be_collection(parent_focus),
         }
     }
+
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        self.len().map(|len| len == 0)
+    }
 }
@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use super::spot::Spot;
+use crate::error::Error;
+use crate::node::NodeValue;
+
+
+impl Spot {
+    /// 将JSON文本解析后整体挂载到该位置，作为一次变更记入日志
+    pub fn set_json(self, json: &str) -> Result<Spot, Error> {
+        let new_value_node = match serde_json::from_str::<NodeValue>(json) {
+            Ok(value) => Arc::new(value),
+            Err(err) => return Error::json_error(err),
+        };
+
+        self.set_value_node(new_value_node)
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        match serde_json::to_string(self.node.as_ref()) {
+            Ok(json) => Ok(json),
+            Err(err) => Error::json_error(err),
+        }
+    }
+
+    pub fn to_json_pretty(&self) -> Result<String, Error> {
+        match serde_json::to_string_pretty(self.node.as_ref()) {
+            Ok(json) => Ok(json),
+            Err(err) => Error::json_error(err),
+        }
+    }
+}
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use crate::focus::{AccessKey, FocusLocator};
 use crate::node::{NodeValue, MapValue, ListValue};
 use super::spot::Spot;
+use super::gen::absolute_item_focus;
 use crate::error::Error;
 
 
@@ -32,7 +33,9 @@ impl Spot {
         let domain = &self.cone;
         let parent_focus = &self.focus;
         let parent_node = &self.node;            
-        
+
+        let _writer = domain.lock_writer();
+
         let (new_index, new_parent_node) = match parent_node.as_ref() {
             NodeValue::List(list_value) => {
                 let index = list_value.len();
@@ -47,6 +50,8 @@ impl Spot {
 
         let item_focus = parent_focus.focus(new_index);
 
+        domain.check_conflict(parent_focus, Some(parent_node), Some(&new_parent_node))?;
+
         self.cone.log_listitem_inserted(
             &item_focus, 
             parent_node, 
@@ -54,12 +59,12 @@ impl Spot {
             &new_parent_node
         );
 
-        Ok(Spot {
-            cone: domain.clone(),
-            focus: parent_focus.clone(),
-            node: new_parent_node,
-            parent: self.parent,
-        })          
+        Ok(Spot::new(
+            domain.clone(),
+            parent_focus.clone(),
+            new_parent_node,
+            self.parent,
+        ))          
     }
 
 
@@ -68,19 +73,31 @@ impl Spot {
         access_key: K, 
         item_node: V
     ) -> Result<Self, Error> {
-        
+
+        let new_item_node = Arc::new(item_node.into());
+        self.insert_item_node(access_key.into(), new_item_node)
+    }
+
+    pub(crate) fn insert_item_node(
+        self, 
+        access_key: AccessKey, 
+        new_item_node: Arc<NodeValue>
+    ) -> Result<Self, Error> {
         
         let domain = &self.cone;
         let parent_focus = &self.focus;
         let parent_node = &self.node;            
-        
-        let access_key = access_key.into();
-        let item_focus = parent_focus.focus(access_key.clone());
 
-        let new_item_node = Arc::new(item_node.into());
+        let _writer = domain.lock_writer();
+
+        let item_focus = absolute_item_focus(parent_node, &parent_focus.focus(access_key.clone()));
 
         let new_parent_node = match (parent_node.as_ref(), access_key) {
             (NodeValue::List(list_value), AccessKey::Index(index)) => {
+                let absolute_index = list_value.absolute_index(index);
+                if absolute_index < 0 || absolute_index > list_value.len() {
+                    return Error::no_such_item(parent_focus, &AccessKey::Index(index));
+                }
 
                 let new_list = list_value.insert(index, new_item_node.clone());
                 let new_parent_node = Arc::new(NodeValue::List(new_list));
@@ -89,6 +106,8 @@ impl Spot {
             _ => Error::should_be_list(parent_focus)
         }?;
 
+        domain.check_conflict(parent_focus, Some(parent_node), Some(&new_parent_node))?;
+
         self.cone.log_listitem_inserted(
             &item_focus, 
             parent_node,
@@ -96,12 +115,12 @@ impl Spot {
             &new_parent_node
         );
 
-        Ok(Spot {
-            cone: domain.clone(),
-            focus: parent_focus.clone(),
-            node: new_parent_node,
-            parent: self.parent,
-        })
+        Ok(Spot::new(
+            domain.clone(),
+            parent_focus.clone(),
+            new_parent_node,
+            self.parent,
+        ))
 
     }
 }
@@ -0,0 +1,114 @@
+// RFC 7386 JSON Merge Patch 以及分层深度合并
+use std::sync::Arc;
+
+use super::spot::Spot;
+use crate::diff::diff_nodes;
+use crate::domain::Cone;
+use crate::error::Error;
+use crate::focus::{Focus, FocusLocator};
+use crate::merge::{MergeStrategy, ScalarMerge, merge_nodes};
+use crate::node::{NodeValue, MapValue};
+
+
+impl Spot {
+    /// 将merge patch合并到该位置：Map逐个key递归合并，值为None的key被删除，
+    /// 其他值整体替换。每一处修改都作为单独的变更记入日志
+    pub fn merge_patch<V: Into<NodeValue>>(self, patch: V) -> Result<Spot, Error> {
+        let patch = Arc::new(patch.into());
+        merge_patch_at(&self.cone, &self.focus, &patch)?;
+
+        Spot::locate(&self.cone, &self.focus)
+    }
+
+    pub fn merge_patch_json(self, patch: &str) -> Result<Spot, Error> {
+        match serde_json::from_str::<NodeValue>(patch) {
+            Ok(patch) => self.merge_patch(patch),
+            Err(err) => Error::json_error(err),
+        }
+    }
+
+    /// 将other位置的子树按strategy深度合并到该位置，同时返回发生冲突的位置。
+    /// 冲突策略为Error时，只要存在冲突就返回MergeConflict且不做修改；
+    /// 否则合并结果与当前子树的差异逐项写入并记入日志
+    pub fn deep_merge(self, other: &Spot, strategy: MergeStrategy) -> Result<(Spot, Vec<Arc<Focus>>), Error> {
+        let target = Spot::locate(&self.cone, &self.focus)?;
+
+        let mut conflicts = Vec::new();
+        let merged = merge_nodes(&target.focus, &target.node, &other.node, &strategy, &mut conflicts);
+
+        if strategy.scalars == ScalarMerge::Error && !conflicts.is_empty() {
+            return Error::merge_conflict(conflicts);
+        }
+
+        let mut ops = Vec::new();
+        diff_nodes(&target.focus, &target.node, &merged, &mut ops);
+        self.apply_writes(ops.into_iter().map(Into::into).collect())?;
+
+        Ok((Spot::locate(&self.cone, &self.focus)?, conflicts))
+    }
+}
+
+fn merge_patch_at(cone: &Arc<Cone>, focus: &Arc<Focus>, patch: &Arc<NodeValue>) -> Result<(), Error> {
+    let target = Spot::locate(cone, focus)?;
+
+    let patch_map = match patch.as_ref() {
+        NodeValue::Map(patch_map) => patch_map,
+        _ => {
+            if !target.node.deep_eq(patch) {
+                target.set_value_node(patch.clone())?;
+            }
+            return Ok(());
+        }
+    };
+
+    let mut target = match target.node.as_ref() {
+        NodeValue::Map(_) => target,
+        _ => target.set_value_node(Arc::new(NodeValue::Map(MapValue::new())))?,
+    };
+
+    let mut keys = patch_map.map.keys().cloned().collect::<Vec<String>>();
+    keys.sort();
+
+    for key in keys {
+        let value = patch_map.get_item(&key).unwrap();
+        let existing = match target.node.as_ref() {
+            NodeValue::Map(target_map) => target_map.get_item(&key).cloned(),
+            _ => None,
+        };
+
+        target = match (value.as_ref(), existing) {
+            (NodeValue::None, Some(_)) => target.remove(key)?,
+            (NodeValue::None, None) => target,
+            (NodeValue::Map(_), Some(ref existing)) if is_map(existing) => {
+                merge_patch_at(cone, &focus.focus(key), value)?;
+                Spot::locate(cone, focus)?
+            },
+            (_, Some(ref existing)) if existing.deep_eq(value) => target,
+            (_, _) => target.set_item_node(key.into(), merge_patch_value(value))?,
+        };
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn is_map(node: &Arc<NodeValue>) -> bool {
+    matches!(node.as_ref(), NodeValue::Map(_))
+}
+
+/// 合并到一个不存在的目标上：去掉各层Map中值为None的key
+fn merge_patch_value(patch: &Arc<NodeValue>) -> Arc<NodeValue> {
+    match patch.as_ref() {
+        NodeValue::Map(patch_map) => {
+            let mut new_map = MapValue::new();
+            for (key, value) in patch_map.map.iter() {
+                if let NodeValue::None = value.as_ref() {
+                    continue;
+                }
+                new_map = new_map.set_item(key.to_string(), merge_patch_value(value)).0;
+            }
+            Arc::new(NodeValue::Map(new_map))
+        },
+        _ => patch.clone(),
+    }
+}
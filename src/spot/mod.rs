@@ -4,6 +4,14 @@ mod value;
 mod gen;
 mod navigate;
 mod list;
+mod json;
+mod diff;
+mod patch;
+mod merge;
+mod children;
+mod walk;
+mod cas;
 
 pub use spot::Spot;
+pub use walk::{Visitor, Walk};
 
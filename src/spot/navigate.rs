@@ -1,9 +1,11 @@
 
-use crate::focus::{AccessKey, FocusLocator, FocusTurnTo};
+use std::sync::Arc;
+
+use crate::focus::{AccessKey, Focus, FocusLocator, FocusTurnTo};
 use super::spot::{Spot};
 use crate::error::Error;
 
-use crate::domain::get_item_node;
+use crate::domain::{Cone, get_item_node};
 
 
 impl Spot {
@@ -12,24 +14,24 @@ impl Spot {
         let access_key = access_key.into();
         let item_focus = self.focus.focus(access_key.clone());
 
-        Ok(Spot {
-            cone: self.cone,
-            focus: item_focus,
-            node: get_item_node(&self.focus, &self.node, &access_key)?,
-            parent: Some(self.node),
-        })
+        Ok(Spot::new(
+            self.cone,
+            item_focus,
+            get_item_node(&self.focus, &self.node, &access_key)?,
+            Some(self.node),
+        ))
     }
 
     pub fn navigate(&self, path: &str) -> Result<Spot, Error> {
         match self.focus.turn_to(path) {
             Ok(ref to_focus) => {
                 let (parent_node, new_node) = self.cone.get_focus_node(to_focus)?;
-                Ok(Spot {
-                    cone: self.cone.clone(),
-                    focus: to_focus.clone(),
-                    node: new_node,
-                    parent: parent_node,
-                })
+                Ok(Spot::new(
+                    self.cone.clone(),
+                    to_focus.clone(),
+                    new_node,
+                    parent_node,
+                ))
             },
             Err(err) => {
                 Err(Error::AccessPathError(err))
@@ -37,4 +39,9 @@ impl Spot {
         }
     }
 
-}
\ No newline at end of file
+    /// 取得focus处最新的节点
+    pub(crate) fn locate(cone: &Arc<Cone>, focus: &Arc<Focus>) -> Result<Spot, Error> {
+        let (parent_node, node) = cone.get_focus_node(focus)?;
+        Ok(Spot::new(cone.clone(), focus.clone(), node, parent_node))
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use super::spot::Spot;
+use crate::error::Error;
+use crate::focus::{Focus, FocusLocator};
+use crate::node::NodeValue;
+use crate::patch::{PatchWrite, parse_patch, plan_patch};
+
+
+impl Spot {
+    /// 应用RFC 6902 JSON Patch，路径相对于该位置。
+    /// 先在当前节点上试运行全部操作，任何一步失败（包括test）都不做修改；
+    /// 成功后每个操作经由set_item/insert_item/remove写入并记入日志
+    pub fn apply_patch(self, patch: &str) -> Result<Spot, Error> {
+        let patch = match serde_json::from_str::<NodeValue>(patch) {
+            Ok(patch) => patch,
+            Err(err) => return Error::json_error(err),
+        };
+
+        let ops = parse_patch(&patch)?;
+
+        let target = Spot::locate(&self.cone, &self.focus)?;
+        let writes = plan_patch(&target.focus, &target.node, &ops)?;
+
+        self.apply_writes(writes)?;
+
+        Spot::locate(&self.cone, &self.focus)
+    }
+
+    /// 依次执行写操作，每一步都重新定位到最新的父节点
+    pub(crate) fn apply_writes(&self, writes: Vec<PatchWrite>) -> Result<(), Error> {
+        for write in writes {
+            self.apply_patch_write(write)?;
+        }
+        Ok(())
+    }
+
+    fn apply_patch_write(&self, write: PatchWrite) -> Result<Spot, Error> {
+        match write {
+            PatchWrite::Set { focus, value } => {
+                match focus.get_parent() {
+                    Some(parent_focus) => {
+                        Spot::locate(&self.cone, parent_focus)?
+                            .set_item_node(focus.get_access_key(), value)
+                    },
+                    None => {
+                        Spot::locate(&self.cone, &focus)?.set_value_node(value)
+                    },
+                }
+            },
+            PatchWrite::Insert { focus, value } => {
+                Spot::locate(&self.cone, parent_of(&focus))?
+                    .insert_item_node(focus.get_access_key(), value)
+            },
+            PatchWrite::Remove { focus } => {
+                Spot::locate(&self.cone, parent_of(&focus))?
+                    .remove(focus.get_access_key())
+            },
+        }
+    }
+}
+
+#[inline]
+fn parent_of(focus: &Arc<Focus>) -> &Arc<Focus> {
+    focus.get_parent().expect("the item of a collection should have a parent")
+}
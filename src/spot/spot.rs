@@ -8,5 +8,37 @@ pub struct Spot {
     pub(crate) cone: Arc<Cone>,
     pub(crate) parent: Option<Arc<NodeValue>>,
     pub(crate) node: Arc<NodeValue>,
-    pub(crate) focus: Arc<Focus>
+    pub(crate) focus: Arc<Focus>,
+    pub(crate) version: u64, // 取得该spot时focus处的版本
+}
+
+impl Spot {
+    pub(crate) fn new(
+        cone: Arc<Cone>,
+        focus: Arc<Focus>,
+        node: Arc<NodeValue>,
+        parent: Option<Arc<NodeValue>>,
+    ) -> Spot {
+        let version = cone.version_of_focus(&focus);
+        Spot { cone, parent, node, focus, version }
+    }
+
+    /// 该位置在focus树中对应的节点
+    #[inline]
+    pub fn get_focus(&self) -> &Arc<Focus> {
+        &self.focus
+    }
+
+    /// 取得该spot时所见的节点，可作为compare_and_set的期望值
+    #[inline]
+    pub fn get_node(&self) -> &Arc<NodeValue> {
+        &self.node
+    }
+
+    /// 取得该spot时，该位置或其下最近一次被修改时的txid，
+    /// 与get_node对应，可用作etag，参见`Domain::version_of`
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
 }
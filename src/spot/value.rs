@@ -1,4 +1,7 @@
-use crate::node::NodeValue;
+use std::convert::TryFrom;
+
+use crate::error::Error;
+use crate::node::{NodeValue, StructuralKey};
 use super::spot::Spot;
 
 impl Spot {
@@ -17,6 +20,32 @@ impl Spot {
         }
     }
 
+    pub fn is_bool(&self) -> bool {
+        matches!(self.node.as_ref(), NodeValue::Bool(_))
+    }
+
+    /// 只有Float为true。整数不是浮点数，但可以用as_f64读取，参见is_number
+    pub fn is_float(&self) -> bool {
+        matches!(self.node.as_ref(), NodeValue::Float(_))
+    }
+
+    /// Integer或Float，即as_f64能够读取的值
+    pub fn is_number(&self) -> bool {
+        matches!(self.node.as_ref(), NodeValue::Integer(_) | NodeValue::Float(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self.node.as_ref(), NodeValue::String(_))
+    }
+
+    pub fn is_list(&self) -> bool {
+        matches!(self.node.as_ref(), NodeValue::List(_))
+    }
+
+    pub fn is_map(&self) -> bool {
+        matches!(self.node.as_ref(), NodeValue::Map(_))
+    }
+
     pub fn to_bool(&self) -> bool {
         if let NodeValue::Bool(value) = self.node.as_ref() {
             return *value;
@@ -50,6 +79,101 @@ impl Spot {
     }
 }
 
+impl Spot {
+    pub fn as_bool(&self) -> Result<bool, Error> {
+        match self.node.as_ref() {
+            NodeValue::Bool(value) => Ok(*value),
+            node => Error::type_mismatch(&self.focus, "bool", node.type_name()),
+        }
+    }
+
+    pub fn as_i64(&self) -> Result<i64, Error> {
+        match self.node.as_ref() {
+            NodeValue::Integer(value) => Ok(*value),
+            node => Error::type_mismatch(&self.focus, "integer", node.type_name()),
+        }
+    }
+
+    /// 读取is_number为true的值：JSON中的`1`会被解析为Integer，
+    /// 因此整数也放宽为浮点数读取，超过2^53的整数会损失精度
+    pub fn as_f64(&self) -> Result<f64, Error> {
+        match self.node.as_ref() {
+            NodeValue::Float(value) => Ok(*value),
+            NodeValue::Integer(value) => Ok(*value as f64),
+            node => Error::type_mismatch(&self.focus, "float", node.type_name()),
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, Error> {
+        match self.node.as_ref() {
+            NodeValue::String(value) => Ok(value.as_str()),
+            node => Error::type_mismatch(&self.focus, "string", node.type_name()),
+        }
+    }
+
+    /// 按目标类型读取该位置的值，类型不符时返回TypeMismatch
+    pub fn try_into<T>(&self) -> Result<T, Error> 
+        where T: for<'a> TryFrom<&'a Spot, Error = Error>
+    {
+        T::try_from(self)
+    }
+}
+
+impl TryFrom<&Spot> for bool {
+    type Error = Error;
+
+    fn try_from(spot: &Spot) -> Result<Self, Error> {
+        spot.as_bool()
+    }
+}
+
+impl TryFrom<&Spot> for i64 {
+    type Error = Error;
+
+    fn try_from(spot: &Spot) -> Result<Self, Error> {
+        spot.as_i64()
+    }
+}
+
+impl TryFrom<&Spot> for f64 {
+    type Error = Error;
+
+    fn try_from(spot: &Spot) -> Result<Self, Error> {
+        spot.as_f64()
+    }
+}
+
+impl TryFrom<&Spot> for String {
+    type Error = Error;
+
+    fn try_from(spot: &Spot) -> Result<Self, Error> {
+        spot.as_str().map(|value| value.to_string())
+    }
+}
+
+impl TryFrom<&Spot> for NodeValue {
+    type Error = Error;
+
+    fn try_from(spot: &Spot) -> Result<Self, Error> {
+        Ok(spot.node.as_ref().clone())
+    }
+}
+
+impl Spot {
+    /// 按内容比较两个位置上的子树，可以跨domain
+    pub fn deep_eq(&self, other: &Spot) -> bool {
+        self.node.deep_eq(&other.node)
+    }
+
+    pub fn content_digest(&self) -> u64 {
+        self.node.content_digest()
+    }
+
+    pub fn structural_key(&self) -> StructuralKey {
+        StructuralKey::new(self.node.clone())
+    }
+}
+
 impl ::std::fmt::Debug for Spot {
     fn fmt(&self, fmt: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         self.node.fmt(fmt)
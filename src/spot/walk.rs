@@ -0,0 +1,125 @@
+use super::spot::Spot;
+use crate::node::NodeValue;
+
+
+/// 深度优先遍历时的回调。enter_map/enter_list在子项之前调用，
+/// 返回false则跳过该子树且不再调用exit；exit在全部子项之后调用
+pub trait Visitor {
+    fn enter_map(&mut self, _spot: &Spot) -> bool {
+        true
+    }
+
+    fn enter_list(&mut self, _spot: &Spot) -> bool {
+        true
+    }
+
+    fn leaf(&mut self, _spot: &Spot) {}
+
+    fn exit(&mut self, _spot: &Spot) {}
+}
+
+impl Spot {
+    /// 以该位置为根，深度优先地访问整棵子树，Map的子项按key排序
+    pub fn accept<V: Visitor>(&self, visitor: &mut V) {
+        let entered = match self.node.as_ref() {
+            NodeValue::Map(_) => visitor.enter_map(self),
+            NodeValue::List(_) => visitor.enter_list(self),
+            _ => {
+                visitor.leaf(self);
+                return;
+            },
+        };
+
+        if !entered {
+            return;
+        }
+
+        for (_, child) in self.children().expect("the node should be a collection") {
+            child.accept(visitor);
+        }
+        visitor.exit(self);
+    }
+
+    /// 深度优先遍历该子树的迭代器，默认先序（父节点在子项之前）
+    pub fn walk(&self) -> Walk {
+        Walk {
+            stack: vec![(self.shallow_clone(), false)],
+            post_order: false,
+            last_children: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn shallow_clone(&self) -> Spot {
+        Spot {
+            cone: self.cone.clone(),
+            parent: self.parent.clone(),
+            node: self.node.clone(),
+            focus: self.focus.clone(),
+            version: self.version,
+        }
+    }
+}
+
+pub struct Walk {
+    /// 待访问的位置，以及其子项是否已经展开（仅后序使用）
+    stack: Vec<(Spot, bool)>,
+    post_order: bool,
+    /// 先序时上一次返回的位置的子项，在下一次next时才入栈，以便skip_children
+    last_children: Vec<Spot>,
+}
+
+impl Walk {
+    /// 改为后序遍历，子项在父节点之前返回
+    pub fn post_order(mut self) -> Self {
+        self.post_order = true;
+        self
+    }
+
+    /// 先序遍历时跳过上一次返回的位置的子树，后序遍历时无效
+    pub fn skip_children(&mut self) {
+        self.last_children.clear();
+    }
+
+    fn next_pre_order(&mut self) -> Option<Spot> {
+        while let Some(child) = self.last_children.pop() {
+            self.stack.push((child, false));
+        }
+
+        let (spot, _) = self.stack.pop()?;
+        if let Ok(children) = spot.children() {
+            self.last_children = children.map(|(_, child)| child).collect();
+        }
+        Some(spot)
+    }
+
+    fn next_post_order(&mut self) -> Option<Spot> {
+        loop {
+            let (spot, expanded) = self.stack.pop()?;
+            if expanded {
+                return Some(spot);
+            }
+
+            match spot.children() {
+                Ok(children) => {
+                    let children = children.map(|(_, child)| child).collect::<Vec<Spot>>();
+                    self.stack.push((spot, true));
+                    self.stack.extend(children.into_iter().rev().map(|child| (child, false)));
+                },
+                Err(_) => return Some(spot),
+            }
+        }
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Spot;
+
+    fn next(&mut self) -> Option<Spot> {
+        if self.post_order {
+            self.next_post_order()
+        } else {
+            self.next_pre_order()
+        }
+    }
+}
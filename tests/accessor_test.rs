@@ -0,0 +1,43 @@
+use dcone::{Domain, Error};
+use dcone::focus::FocusLocator;
+
+#[test]
+fn typed_accessors() -> Result<(), Error> {
+    let domain = Domain::from_json(r#"
+        {"port": 5432, "ratio": 0.5, "name": "db", "debug": true, "tags": [], "opts": {}, "none": null}
+    "#)?;
+
+    let port = domain.navigate("/port")?;
+    assert_eq!(port.as_i64()?, 5432);
+    assert_eq!(port.as_f64()?, 5432.0);
+    assert_eq!(port.try_into::<i64>()?, 5432);
+
+    assert_eq!(domain.navigate("/ratio")?.try_into::<f64>()?, 0.5);
+    assert_eq!(domain.navigate("/name")?.as_str()?, "db");
+    assert_eq!(domain.navigate("/name")?.try_into::<String>()?, "db");
+    assert!(domain.navigate("/debug")?.try_into::<bool>()?);
+
+    match domain.navigate("/name")?.as_i64() {
+        Err(Error::TypeMismatch { focus, expected, found }) => {
+            assert_eq!(focus.access_path(), "/name");
+            assert_eq!((expected, found), ("integer", "string"));
+        },
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(domain.navigate("/ratio")?.as_bool().is_err());
+    assert!(domain.navigate("/none")?.try_into::<String>().is_err());
+
+    assert!(domain.navigate("/debug")?.is_bool());
+    assert!(domain.navigate("/ratio")?.is_float());
+
+    // 整数不是浮点数，但可以放宽为浮点数读取
+    assert!(!port.is_float());
+    assert!(port.is_number() && domain.navigate("/ratio")?.is_number());
+    assert!(!domain.navigate("/name")?.is_number());
+    assert!(domain.navigate("/name")?.is_string());
+    assert!(domain.navigate("/tags")?.is_list());
+    assert!(domain.navigate("/opts")?.is_map());
+    assert!(!domain.navigate("/opts")?.is_list());
+
+    Ok(())
+}
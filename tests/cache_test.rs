@@ -0,0 +1,126 @@
+use dcone::{Domain, Error};
+
+const DEEP: &str = r#"{
+    "a": {"b": {"c": {"d": 1, "e": 2}}, "x": 0},
+    "list": [{"v": 0}, {"v": 1}]
+}"#;
+
+#[test]
+fn repeated_reads_hit_the_cache() -> Result<(), Error> {
+
+    let domain = Domain::from_json(DEEP)?;
+
+    assert_eq!(domain.navigate("/a/b/c/d")?.to_json()?, "1");
+    let first = domain.cache_stats();
+    assert!(first.misses > 0);
+
+    for _ in 0..10 {
+        assert_eq!(domain.navigate("/a/b/c/d")?.to_json()?, "1");
+    }
+
+    let stats = domain.cache_stats();
+    assert_eq!(stats.misses, first.misses);
+    assert!(stats.hits >= first.hits + 10);
+    assert!(stats.hit_rate() > 0.5);
+
+    Ok(())
+}
+
+#[test]
+fn writes_invalidate_ancestors_and_descendants_only() -> Result<(), Error> {
+
+    let domain = Domain::from_json(DEEP)?;
+
+    domain.navigate("/a/b/c/d")?;
+    domain.navigate("/a/b/c/e")?;
+
+    // 兄弟分支上的写入不影响/a/b/c/d
+    domain.navigate("/a")?.set_item("x", 10)?;
+    let before = domain.cache_stats();
+    assert_eq!(domain.navigate("/a/b/c/d")?.to_json()?, "1");
+    assert_eq!(domain.cache_stats().misses, before.misses);
+
+    // 写入/a/b/c/d后，它和它的祖先重新查找，兄弟/a/b/c/e仍然命中
+    domain.navigate("/a/b/c")?.set_item("d", 100)?;
+    let before = domain.cache_stats();
+    assert_eq!(domain.navigate("/a/b/c/d")?.to_json()?, "100");
+    assert!(domain.cache_stats().misses > before.misses);
+
+    let before = domain.cache_stats();
+    assert_eq!(domain.navigate("/a/b/c/e")?.to_json()?, "2");
+    assert_eq!(domain.cache_stats().misses, before.misses);
+
+    // 替换上层节点后，其下的全部路径失效
+    domain.navigate("/a")?.set_item("b", 5)?;
+    assert!(domain.navigate("/a/b/c/d").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn list_insertions_shift_cached_items() -> Result<(), Error> {
+
+    let domain = Domain::from_json(DEEP)?;
+
+    assert_eq!(domain.navigate("/list#0/v")?.to_json()?, "0");
+    assert_eq!(domain.navigate("/list#1/v")?.to_json()?, "1");
+
+    domain.navigate("/list")?.insert_item(0, 9)?;
+
+    assert_eq!(domain.navigate("/list#0")?.to_json()?, "9");
+    assert_eq!(domain.navigate("/list#1/v")?.to_json()?, "0");
+
+    Ok(())
+}
+
+#[test]
+fn rollback_and_undo_reset_the_cache() -> Result<(), Error> {
+
+    let domain = Domain::from_json(DEEP)?;
+    assert_eq!(domain.navigate("/a/b/c/d")?.to_json()?, "1");
+
+    let result: Result<(), Error> = domain.transaction(|tx| {
+        tx.navigate("/a/b/c")?.set_item("d", 7)?;
+        assert_eq!(tx.navigate("/a/b/c/d")?.to_json()?, "7");
+        tx.navigate("/missing")?;
+        Ok(())
+    });
+    assert!(result.is_err());
+    assert_eq!(domain.navigate("/a/b/c/d")?.to_json()?, "1");
+
+    domain.navigate("/a/b/c")?.set_item("d", 8)?;
+    assert_eq!(domain.navigate("/a/b/c/d")?.to_json()?, "8");
+    assert!(domain.undo()?);
+    assert_eq!(domain.navigate("/a/b/c/d")?.to_json()?, "1");
+
+    Ok(())
+}
+
+#[test]
+fn entries_are_bounded() -> Result<(), Error> {
+
+    let items = (0..100).map(|n| format!(r#"{{"v": {}}}"#, n)).collect::<Vec<_>>();
+    let domain = Domain::from_json(&format!(r#"{{"hot": 1, "list": [{}]}}"#, items.join(",")))?;
+    domain.set_cache_capacity(16);
+    assert_eq!(domain.cache_stats().capacity, 16);
+
+    domain.navigate("/hot")?;
+    for n in 0..100 {
+        domain.navigate(&format!("/list#{}/v", n))?;
+        assert!(domain.cache_stats().entries <= 16);
+
+        // 经常读取的位置不会被移除
+        let before = domain.cache_stats();
+        domain.navigate("/hot")?;
+        assert_eq!(domain.cache_stats().misses, before.misses);
+    }
+
+    domain.set_cache_capacity(4);
+    assert!(domain.cache_stats().entries <= 4);
+
+    domain.set_cache_capacity(0);
+    domain.navigate("/hot")?;
+    assert_eq!(domain.cache_stats().entries, 0);
+
+    Ok(())
+}
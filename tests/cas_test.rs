@@ -0,0 +1,101 @@
+use std::sync::Arc;
+use std::thread;
+
+use dcone::{Domain, Error, NodeValue};
+use dcone::focus::FocusLocator;
+
+#[test]
+fn set_if_unchanged_succeeds_without_interference() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"replicas": 1, "image": "a"}"#)?;
+
+    let replicas = domain.navigate("/replicas")?;
+    let next = replicas.as_i64()? + 1;
+
+    // 兄弟节点的修改不算冲突
+    domain.root().set_item("image", "b")?;
+    replicas.set_if_unchanged(next)?;
+
+    assert_eq!(domain.root().to_json()?, r#"{"image":"b","replicas":2}"#);
+
+    Ok(())
+}
+
+#[test]
+fn set_if_unchanged_reports_the_current_value() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"replicas": 1}"#)?;
+
+    let replicas = domain.navigate("/replicas")?;
+    domain.root().set_item("replicas", 5)?;
+
+    match replicas.set_if_unchanged(2) {
+        Err(Error::Conflict { focus, ours, theirs }) => {
+            assert_eq!(focus.access_path(), "/replicas");
+            assert!(matches!(ours.as_ref(), NodeValue::Integer(2)));
+            assert!(matches!(theirs.as_ref(), NodeValue::Integer(5)));
+        },
+        _ => panic!("expected a conflict"),
+    }
+    assert_eq!(domain.root().to_json()?, r#"{"replicas":5}"#);
+
+    Ok(())
+}
+
+#[test]
+fn compare_and_set_with_an_expected_node() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"spec": {"replicas": 1}}"#)?;
+
+    let expected = domain.navigate("/spec")?.get_node().clone();
+    domain.navigate("/spec")?.set_item("replicas", 2)?;
+
+    // /spec已被修改
+    let result = domain.navigate("/spec")?.compare_and_set(&expected, "replaced");
+    assert!(matches!(result, Err(Error::Conflict { .. })));
+
+    let expected = domain.navigate("/spec")?.get_node().clone();
+    domain.navigate("/spec")?.compare_and_set(&expected, "replaced")?;
+    assert_eq!(domain.root().to_json()?, r#"{"spec":"replaced"}"#);
+
+    // 已被删除的位置
+    let removed = domain.navigate("/spec")?;
+    domain.root().remove("spec")?;
+    assert!(matches!(
+        removed.set_if_unchanged(1),
+        Err(Error::Conflict { theirs, .. }) if matches!(theirs.as_ref(), NodeValue::None)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_increments_retry_on_conflict() -> Result<(), Error> {
+
+    let domain = Arc::new(Domain::from_json(r#"{"count": 0}"#)?);
+
+    let workers = (0..4).map(|_| {
+        let domain = domain.clone();
+        thread::spawn(move || {
+            for _ in 0..25 {
+                loop {
+                    let count = domain.navigate("/count").unwrap();
+                    let next = count.as_i64().unwrap() + 1;
+                    match count.set_if_unchanged(next) {
+                        Ok(_) => break,
+                        Err(Error::Conflict { .. }) => continue,
+                        Err(err) => panic!("{}", err),
+                    }
+                }
+            }
+        })
+    }).collect::<Vec<_>>();
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    assert_eq!(domain.navigate("/count")?.to_json()?, "100");
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use dcone::{Domain, Error};
+use dcone::focus::{AccessKey, FocusLocator};
+
+#[test]
+fn iterate_children() -> Result<(), Error> {
+    let domain = Domain::from_json(r#"{"b": [10, 20], "a": {"x": 1}, "c": "s"}"#)?;
+
+    let keys = domain.root().keys()?.collect::<Vec<AccessKey>>();
+    assert_eq!(keys, vec![AccessKey::from("a"), AccessKey::from("b"), AccessKey::from("c")]);
+
+    let paths = domain.root().children()?
+        .map(|(key, spot)| (key, spot.get_focus().access_path()))
+        .collect::<Vec<(AccessKey, String)>>();
+    assert_eq!(paths, vec![
+        (AccessKey::from("a"), "/a".to_string()),
+        (AccessKey::from("b"), "/b".to_string()),
+        (AccessKey::from("c"), "/c".to_string()),
+    ]);
+
+    let items = domain.navigate("/b")?.children()?
+        .map(|(key, spot)| Ok((key, spot.as_i64()?, spot.get_focus().access_path())))
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert_eq!(items, vec![
+        (AccessKey::Index(0), 10, "/b#0".to_string()),
+        (AccessKey::Index(1), 20, "/b#1".to_string()),
+    ]);
+
+    for (key, spot) in domain.root().children()? {
+        assert!(spot.deep_eq(&domain.root().focus(key)?));
+    }
+
+    let items = domain.root().items()?
+        .map(|(key, spot)| (key, spot.get_focus().access_path()))
+        .collect::<Vec<(AccessKey, String)>>();
+    assert_eq!(items, paths);
+
+    for (key, spot) in domain.navigate("/a")?.items()? {
+        assert_eq!(key, AccessKey::from("x"));
+        assert_eq!(spot.as_i64()?, 1);
+    }
+    assert!(domain.navigate("/c")?.items().is_err());
+
+    assert!(domain.navigate("/c")?.keys().is_err());
+    assert_eq!(domain.navigate("/a/x")?.children().err().map(|err| err.to_string()),
+        Some("The node should be a Map or List: /a/x".to_string()));
+
+    Ok(())
+}
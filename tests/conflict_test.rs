@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use dcone::{ConflictPolicy, Domain, Error, NodeEvent, NodeValue};
+use dcone::focus::FocusLocator;
+
+fn conflicts(domain: &Domain) -> Vec<(String, Option<Arc<NodeValue>>)> {
+    domain.log().log.read().unwrap().iter().filter_map(|event| match event {
+        NodeEvent::Conflict { focus, value, .. } => Some((focus.access_path(), value.clone())),
+        _ => None,
+    }).collect()
+}
+
+fn same_key_from_two_spots(domain: &Domain) -> Result<(), Error> {
+    let first = domain.navigate("/a")?;
+    let second = domain.navigate("/a")?;
+
+    first.set_item("x", 1)?;
+    second.set_item("x", 2)?.set_item("y", 2)?;
+    Ok(())
+}
+
+#[test]
+fn last_writer_wins_by_default() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"x": 0}}"#)?;
+    same_key_from_two_spots(&domain)?;
+
+    assert_eq!(domain.navigate("/a")?.to_json()?, r#"{"x":2,"y":2}"#);
+
+    let conflicts = conflicts(&domain);
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].0, "/a/x");
+    assert!(matches!(conflicts[0].1.as_deref(), Some(NodeValue::Integer(2))));
+
+    // 结果就是后到的写入，导出时不再重复
+    assert_eq!(
+        domain.log().to_json_patch(2..)?,
+        r#"[{"op":"replace","path":"/a/x","value":1},{"op":"replace","path":"/a/x","value":2},{"op":"add","path":"/a/y","value":2}]"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn first_writer_wins() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"x": 0}}"#)?;
+    domain.set_conflict_policy(ConflictPolicy::FirstWriterWins);
+    same_key_from_two_spots(&domain)?;
+
+    assert_eq!(domain.navigate("/a")?.to_json()?, r#"{"x":1,"y":2}"#);
+
+    // 冲突的结果也导出到JSON Patch中，重放得到相同的文档
+    let patch = domain.log().to_json_patch(2..)?;
+    let replay = Domain::from_json(r#"{"a": {"x": 0}}"#)?;
+    replay.root().apply_patch(&patch)?;
+    assert_eq!(replay.root().to_json()?, domain.root().to_json()?);
+
+    Ok(())
+}
+
+#[test]
+fn reject_returns_the_conflict() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"x": 0}}"#)?;
+    domain.set_conflict_policy(ConflictPolicy::Reject);
+
+    let first = domain.navigate("/a")?;
+    let second = domain.navigate("/a")?;
+
+    first.set_item("x", 1)?;
+
+    match second.set_item("x", 2) {
+        Err(Error::Conflict { focus, ours, theirs }) => {
+            assert_eq!(focus.access_path(), "/a/x");
+            assert!(matches!(ours.as_ref(), NodeValue::Integer(2)));
+            assert!(matches!(theirs.as_ref(), NodeValue::Integer(1)));
+        },
+        _ => panic!("expected a conflict"),
+    }
+
+    // 没有冲突的写入照常进行
+    domain.navigate("/a")?.set_item("y", 3)?;
+    let stale = domain.navigate("/a")?;
+    domain.navigate("/a")?.set_item("x", 4)?;
+    stale.set_item("z", 5)?;
+
+    assert_eq!(domain.navigate("/a")?.to_json()?, r#"{"x":4,"y":3,"z":5}"#);
+    assert_eq!(conflicts(&domain).len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn rejected_writes_are_not_exported() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"x": 0}}"#)?;
+    domain.set_conflict_policy(ConflictPolicy::Reject);
+
+    let stale = domain.navigate("/a")?;
+    domain.navigate("/a")?.remove("x")?;
+    assert!(matches!(stale.set_item("x", 1), Err(Error::Conflict { .. })));
+    assert_eq!(conflicts(&domain).len(), 1);
+
+    let replay = Domain::new();
+    replay.root().apply_patch(&domain.log().to_json_patch(..)?)?;
+    assert_eq!(replay.root().to_json()?, r#"{"a":{}}"#);
+
+    Ok(())
+}
+
+#[test]
+fn reject_positional_list_writes() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"items": [1, 2]}"#)?;
+    domain.set_conflict_policy(ConflictPolicy::Reject);
+
+    let stale = domain.navigate("/items")?;
+    domain.navigate("/items")?.remove(0)?;
+
+    assert!(matches!(stale.push_item(3), Err(Error::Conflict { .. })));
+    assert_eq!(domain.navigate("/items")?.to_json()?, "[2]");
+
+    Ok(())
+}
+
+#[test]
+fn custom_resolver() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"x": 0}}"#)?;
+    domain.set_conflict_policy(ConflictPolicy::resolver(|_focus, ours, theirs| {
+        match (ours.map(|v| v.as_ref()), theirs.map(|v| v.as_ref())) {
+            (Some(NodeValue::Integer(a)), Some(NodeValue::Integer(b))) => {
+                Some(Arc::new(NodeValue::Integer(a + b)))
+            },
+            _ => ours.cloned(),
+        }
+    }));
+    same_key_from_two_spots(&domain)?;
+
+    assert_eq!(domain.navigate("/a")?.to_json()?, r#"{"x":3,"y":2}"#);
+    assert!(matches!(conflicts(&domain)[0].1.as_deref(), Some(NodeValue::Integer(3))));
+
+    Ok(())
+}
+
+#[test]
+fn resolver_may_delete() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"x": 0}, "items": [0, 1]}"#)?;
+    domain.set_conflict_policy(ConflictPolicy::resolver(|_focus, _ours, _theirs| None));
+
+    same_key_from_two_spots(&domain)?;
+    assert_eq!(domain.navigate("/a")?.to_json()?, r#"{"y":2}"#);
+
+    let first = domain.navigate("/items")?;
+    let second = domain.navigate("/items")?;
+    first.set_item(0, 10)?;
+    second.set_item(0, 20)?;
+    assert_eq!(domain.navigate("/items")?.to_json()?, "[1]");
+
+    assert!(conflicts(&domain).iter().all(|(_, value)| value.is_none()));
+
+    // 删除也导出到JSON Patch中
+    let replay = Domain::new();
+    replay.root().apply_patch(&domain.log().to_json_patch(..)?)?;
+    assert_eq!(replay.root().to_json()?, domain.root().to_json()?);
+
+    Ok(())
+}
+
+#[test]
+fn conflicts_are_in_the_change_feed() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"x": 0}}"#)?;
+    same_key_from_two_spots(&domain)?;
+    domain.root();
+
+    let changes = domain.log().changes_since(0)?;
+    assert!(changes.iter().any(|event| matches!(event, NodeEvent::Conflict { .. })));
+
+    Ok(())
+}
+
+#[test]
+fn list_write_to_a_removed_item() -> Result<(), Error> {
+
+    for (policy, expected) in [
+        (ConflictPolicy::LastWriterWins, "[10,2,3]"),
+        (ConflictPolicy::FirstWriterWins, "[2,3]"),
+    ] {
+        let domain = Domain::from_json(r#"{"items": [1, 2, 3]}"#)?;
+        domain.set_conflict_policy(policy);
+
+        let stale = domain.navigate("/items")?;
+        domain.navigate("/items")?.remove(0)?;
+        stale.set_item(0, 10)?;
+
+        assert_eq!(domain.navigate("/items")?.to_json()?, expected);
+
+        let replay = Domain::new();
+        replay.root().apply_patch(&domain.log().to_json_patch(..)?)?;
+        assert_eq!(replay.root().to_json()?, domain.root().to_json()?);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use dcone::{Domain, Error};
+
+#[test]
+fn navigate_after_deep_nested_writes() -> Result<(), Error> {
+
+    let domain = Domain::new();
+    let root = domain.root();
+
+    domain
+        .root()
+        .set_empty_map()?
+        .set_map_item("a")?
+        .focus("a")?
+        .set_map_item("b")?
+        .focus("b")?
+        .set_map_item("c")?
+        .focus("c")?
+        .set_item("x", 10)?
+        .set_item("y", 20)?;
+
+    // 写入之前取得的spot也能读到写入的结果
+    assert_eq!(root.navigate("/a/b/c/x")?.to_json()?, "10");
+    assert_eq!(root.navigate("/a/b/c")?.to_json()?, r#"{"x":10,"y":20}"#);
+
+    let c = root.navigate("/a/b/c")?;
+    root.navigate("/a/b/c")?.set_item("x", 11)?;
+    assert_eq!(c.navigate("x")?.to_json()?, "11");
+    assert_eq!(c.navigate("../../b/c/y")?.to_json()?, "20");
+
+    Ok(())
+}
+
+#[test]
+fn navigate_after_writes_in_several_branches() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"b": {"c": 1}}, "d": {"e": [1, 2]}}"#)?;
+    let root = domain.root();
+
+    let c = root.navigate("/a/b")?.set_item("c", 2)?;
+    root.navigate("/d/e")?.push_item(3)?;
+
+    assert_eq!(root.navigate("/a/b/c")?.to_json()?, "2");
+    assert_eq!(root.navigate("/d/e#2")?.to_json()?, "3");
+
+    c.set_item("f", "g")?;
+    assert_eq!(root.navigate("/a/b/f")?.to_json()?, r#""g""#);
+
+    root.navigate("/d/e")?.remove(0)?;
+    assert_eq!(root.navigate("/d/e")?.to_json()?, "[2,3]");
+    assert_eq!(domain.root().to_json()?, r#"{"a":{"b":{"c":2,"f":"g"}},"d":{"e":[2,3]}}"#);
+
+    Ok(())
+}
+
+#[test]
+fn navigate_inside_a_transaction() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"b": {"c": 1}}}"#)?;
+
+    domain.transaction(|tx| {
+        let root = tx.root();
+        root.navigate("/a/b")?.set_item("c", 2)?.set_item("d", 3)?;
+
+        assert_eq!(root.navigate("/a/b/c")?.to_json()?, "2");
+        assert_eq!(root.navigate("/a/b/d")?.to_json()?, "3");
+        Ok(())
+    })?;
+
+    assert_eq!(domain.navigate("/a/b")?.to_json()?, r#"{"c":2,"d":3}"#);
+
+    Ok(())
+}
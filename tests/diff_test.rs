@@ -0,0 +1,84 @@
+use dcone::{Domain, DiffOp};
+use dcone::focus::FocusLocator;
+
+use dcone::Error;
+
+fn summarize(ops: &[DiffOp]) -> Vec<String> {
+    ops.iter().map(|op| {
+        let kind = match op {
+            DiffOp::Added { .. } => "added",
+            DiffOp::Removed { .. } => "removed",
+            DiffOp::Replaced { .. } => "replaced",
+            DiffOp::ListInserted { .. } => "inserted",
+            DiffOp::ListDeleted { .. } => "deleted",
+        };
+        format!("{} {}", kind, op.focus().access_path())
+    }).collect()
+}
+
+#[test]
+fn diff_maps() -> Result<(), Error> {
+
+    let yesterday = Domain::from_json(r#"
+        {"db": {"host": "a", "port": 1}, "cache": {"size": 10}, "old": true}
+    "#)?;
+    let today = Domain::from_json(r#"
+        {"db": {"host": "b", "port": 1}, "cache": {"size": 10}, "new": 1.5}
+    "#)?;
+
+    assert_eq!(
+        summarize(&yesterday.diff(&today)),
+        vec!["replaced /db/host", "added /new", "removed /old"]
+    );
+
+    assert!(yesterday.navigate("/cache")?.diff(&today.navigate("/cache")?).is_empty());
+
+    match &yesterday.navigate("/db")?.diff(&today.navigate("/db")?)[0] {
+        DiffOp::Replaced { old_value, new_value, .. } => {
+            assert_eq!(format!("{:?}", old_value), "String(\"a\")");
+            assert_eq!(format!("{:?}", new_value), "String(\"b\")");
+        },
+        op => panic!("unexpected {:?}", op),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn diff_lists() -> Result<(), Error> {
+
+    let old = Domain::from_json(r#"{"l": [1, 2, 3, 4, 5]}"#)?;
+
+    let new = Domain::from_json(r#"{"l": [1, 9, 3, 4, 5]}"#)?;
+    assert_eq!(summarize(&old.diff(&new)), vec!["replaced /l#1"]);
+
+    let new = Domain::from_json(r#"{"l": [1, 2, 7, 8, 3, 4, 5]}"#)?;
+    assert_eq!(summarize(&old.diff(&new)), vec!["inserted /l#2", "inserted /l#3"]);
+
+    let new = Domain::from_json(r#"{"l": [1, 5]}"#)?;
+    assert_eq!(summarize(&old.diff(&new)), vec!["deleted /l#3", "deleted /l#2", "deleted /l#1"]);
+
+    let new = Domain::from_json(r#"{"l": [1, {"a": 1}, 5]}"#)?;
+    assert_eq!(
+        summarize(&old.diff(&new)),
+        vec!["replaced /l#1", "deleted /l#3", "deleted /l#2"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn diff_versions_of_same_domain() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"b": {"c": 1}}, "big": [1, 2, 3]}"#)?;
+    let before = domain.root();
+
+    domain.navigate("/a/b")?.set_item("c", 2)?;
+    let after = domain.root();
+
+    // 未修改的子树与之前共享同一个Arc，直接跳过
+    assert_eq!(summarize(&before.diff(&after)), vec!["replaced /a/b/c"]);
+    assert!(after.diff(&domain.root()).is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+
+use dcone::{ConflictPolicy, Domain, Error, NodeEvent};
+use dcone::focus::FocusLocator;
+
+fn paths(events: &[NodeEvent]) -> Vec<String> {
+    events.iter().map(|event| event.focus().access_path()).collect()
+}
+
+#[test]
+fn changes_after_a_txid() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": 1, "items": []}"#)?;
+    let start = *domain.log().txid_max.read().unwrap();
+
+    domain.root().set_item("a", 2)?;
+    domain.transaction(|tx| {
+        tx.navigate("/items")?.push_item(1)?.push_item(2)?;
+        Ok(())
+    })?;
+    domain.root().remove("a")?;
+
+    let changes = domain.log().changes_since(start)?;
+    assert_eq!(paths(&changes), vec!["/a", "/items#0", "/items#1", "/a"]);
+    assert!(changes.windows(2).all(|pair| pair[0].txid() <= pair[1].txid()));
+
+    let after_set = changes[0].txid();
+    assert_eq!(paths(&domain.log().changes_since(after_set)?), vec!["/items#0", "/items#1", "/a"]);
+
+    Ok(())
+}
+
+#[test]
+fn open_transactions_are_only_seen_by_their_thread() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": 1}"#)?;
+    let start = *domain.log().txid_max.read().unwrap();
+
+    domain.transaction(|tx| {
+        tx.root().set_item("a", 2)?;
+        assert_eq!(paths(&domain.log().changes_since(start)?), vec!["/a"]);
+
+        thread::scope(|scope| {
+            let other = scope.spawn(|| domain.log().changes_since(start));
+            assert!(other.join().unwrap()?.is_empty());
+            Ok(())
+        })
+    })?;
+
+    assert_eq!(paths(&domain.log().changes_since(start)?), vec!["/a"]);
+
+    Ok(())
+}
+
+#[test]
+fn cursors_resume_from_stored_positions() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"n": 0}"#)?;
+    let log = domain.log();
+
+    log.open_cursor("indexer", 0)?;
+    domain.root().set_item("n", 1)?;
+    domain.root().set_item("n", 2)?;
+
+    let changes = log.read_cursor("indexer")?;
+    assert_eq!(changes.len(), 3);
+
+    // 读取不移动游标
+    assert_eq!(log.read_cursor("indexer")?.len(), 3);
+
+    log.advance_cursor("indexer", changes[1].txid())?;
+    assert_eq!(log.read_cursor("indexer")?.len(), 1);
+
+    // 消费者重启后用保存的位置恢复
+    let saved = log.cursor_position("indexer")?;
+    log.close_cursor("indexer")?;
+    assert!(matches!(log.read_cursor("indexer"), Err(Error::NoSuchCursor(name)) if name == "indexer"));
+
+    domain.root().set_item("n", 3)?;
+    log.open_cursor("indexer", saved)?;
+    assert_eq!(log.read_cursor("indexer")?.len(), 2);
+
+    assert_eq!(log.cursors(), vec![("indexer".to_string(), saved)]);
+
+    Ok(())
+}
+
+#[test]
+fn compacted_positions_are_an_error() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"n": 0}"#)?;
+    let log = domain.log();
+
+    domain.root().set_item("n", 1)?;
+    let txid = *log.txid_max.read().unwrap();
+    domain.root().set_item("n", 2)?;
+
+    domain.compact_log(txid)?;
+    assert_eq!(log.compacted_txid(), txid);
+
+    assert!(matches!(log.changes_since(0), Err(Error::Compacted { txid: 0, compacted }) if compacted == txid));
+    assert_eq!(log.open_cursor("late", txid - 1), Err(Error::Compacted { txid: txid - 1, compacted: txid }));
+
+    let changes = log.changes_since(txid)?;
+    assert_eq!(changes.len(), 1);
+    assert_eq!(domain.root().to_json()?, r#"{"n":2}"#);
+
+    Ok(())
+}
+
+#[test]
+fn cursors_can_be_saved_and_loaded() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"n": 0}"#)?;
+    let log = domain.log();
+
+    log.open_cursor("indexer", 0)?;
+    log.open_cursor("mailer", 0)?;
+    domain.root().set_item("n", 1)?;
+    let txid = *log.txid_max.read().unwrap();
+    log.advance_cursor("indexer", txid)?;
+
+    // 游标只在内存中，由调用者保存
+    let saved = log.cursors();
+    log.close_cursor("indexer")?;
+    log.close_cursor("mailer")?;
+
+    domain.root().set_item("n", 2)?;
+    log.load_cursors(&saved)?;
+    assert_eq!(log.cursors(), saved);
+    assert_eq!(log.read_cursor("indexer")?.len(), 1);
+    assert_eq!(log.read_cursor("mailer")?.len(), 3);
+
+    // 有位置已被移除时一个也不恢复
+    domain.compact_log(txid)?;
+    log.close_cursor("indexer")?;
+    log.close_cursor("mailer")?;
+    assert_eq!(log.load_cursors(&saved), Err(Error::Compacted { txid: 0, compacted: txid }));
+    assert!(log.cursors().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn pending_writes_are_not_in_the_feed() -> Result<(), Error> {
+
+    let domain = Arc::new(Domain::from_json(r#"{"a": {"x": 0}}"#)?);
+    let start = *domain.log().txid_max.read().unwrap();
+
+    // 冲突处理在合并时调用，此时后到的写入还没有合并
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let (weak, sink): (Weak<Domain>, _) = (Arc::downgrade(&domain), seen.clone());
+    domain.set_conflict_policy(ConflictPolicy::resolver(move |_focus, _ours, theirs| {
+        let domain = weak.upgrade().unwrap();
+        *sink.lock().unwrap() = paths(&domain.log().changes_since(start).unwrap());
+        theirs.cloned()
+    }));
+
+    let first = domain.navigate("/a")?;
+    let second = domain.navigate("/a")?;
+    first.set_item("x", 1)?;
+    second.set_item("x", 2)?;
+
+    assert_eq!(*seen.lock().unwrap(), vec!["/a/x"]);
+    assert_eq!(paths(&domain.log().changes_since(start)?), vec!["/a/x", "/a/x", "/a/x"]);
+
+    Ok(())
+}
+
+#[test]
+fn compaction_inside_a_transaction_is_an_error() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"n": 0}"#)?;
+    domain.root().set_item("n", 1)?;
+    let txid = *domain.log().txid_max.read().unwrap();
+
+    domain.transaction(|tx| {
+        tx.root().set_item("n", 2)?;
+        assert_eq!(domain.compact_log(txid), Err(Error::InTransaction));
+        Ok(())
+    })?;
+    assert_eq!(domain.log().compacted_txid(), 0);
+
+    domain.compact_log(txid)?;
+    assert_eq!(domain.log().compacted_txid(), txid);
+
+    Ok(())
+}
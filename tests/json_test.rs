@@ -0,0 +1,89 @@
+use dcone::{Domain, NodeEvent};
+
+use dcone::Error;
+
+#[test]
+fn import_and_dump() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"
+        {"db": {"host": "localhost", "port": 5432, "ratio": 0.5},
+         "tags": ["a", "b", null, true]}
+    "#)?;
+
+    assert_eq!(domain.navigate("/db/port")?.to_i64(), 5432);
+    assert_eq!(domain.navigate("/db/ratio")?.to_f64(), 0.5);
+    assert_eq!(domain.navigate("/tags#1")?.to_string(), "b");
+    assert!(domain.navigate("/tags#2")?.is_none());
+    assert!(domain.navigate("/tags#3")?.to_bool());
+
+    assert_eq!(
+        domain.root().to_json()?,
+        r#"{"db":{"host":"localhost","port":5432,"ratio":0.5},"tags":["a","b",null,true]}"#
+    );
+
+    assert_eq!(domain.navigate("/tags")?.to_json_pretty()?, "[\n  \"a\",\n  \"b\",\n  null,\n  true\n]");
+
+    Ok(())
+}
+
+#[test]
+fn import_is_logged() -> Result<(), Error> {
+
+    let json = r#"{"db": {"port": 5432}, "tags": ["a"]}"#;
+    let domain = Domain::from_json(json)?;
+
+    // 导入记为一次对根节点的修改，记录的值即导入的整个文档
+    let log = domain.log().log.read().unwrap().clone();
+    let value = match log.as_slice() {
+        [NodeEvent::RootUpdated { txid: 1, value, .. }] => value.clone(),
+        other => panic!("expected one RootUpdated event, found {} events", other.len()),
+    };
+
+    let replay = Domain::new();
+    replay.root().set_json(&serde_json::to_string(value.as_ref()).unwrap())?;
+    assert_eq!(replay.root().to_json()?, domain.root().to_json()?);
+
+    Ok(())
+}
+
+#[test]
+fn mount_subtree() -> Result<(), Error> {
+
+    let domain = Domain::new();
+    domain
+        .root()
+        .set_empty_map()?
+        .set_map_item("services")?
+        .focus("services")?
+        .set_map_item("web")?
+        .focus("web")?
+        .set_json(r#"{"replicas": 3, "ports": [80, 443]}"#)?;
+
+    assert_eq!(domain.navigate("/services/web/replicas")?.to_i64(), 3);
+    assert_eq!(domain.navigate("/services/web/ports#1")?.to_i64(), 443);
+
+    // 挂载后的子树可以继续编辑
+    domain.navigate("/services/web")?.set_item("replicas", 5)?;
+    domain.navigate("/services/web/ports")?.push_item(8080)?;
+
+    assert_eq!(
+        domain.navigate("/services")?.to_json()?,
+        r#"{"web":{"ports":[80,443,8080],"replicas":5}}"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn invalid_json() {
+
+    match Domain::from_json(r#"{"a": 1"#) {
+        Err(Error::JsonError(_)) => {},
+        _ => panic!("should be a JsonError"),
+    }
+
+    match Domain::from_json("18446744073709551615") {
+        Err(Error::JsonError(_)) => {},
+        _ => panic!("integer out of i64 range should be rejected"),
+    }
+}
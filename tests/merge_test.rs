@@ -0,0 +1,138 @@
+use dcone::{Domain, NodeValue, MapValue, MergeStrategy, ListMerge, ScalarMerge};
+use dcone::focus::FocusLocator;
+use std::sync::Arc;
+
+use dcone::Error;
+
+#[test]
+fn rfc7386_examples() -> Result<(), Error> {
+
+    let cases = [
+        (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+        (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+        (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+        (r#"{"a":{"b":"c"}}"#, r#"{"a":{"b":"d","c":null}}"#, r#"{"a":{"b":"d"}}"#),
+        (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+        (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+        (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+        (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+        (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+        (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"a":1,"e":null}"#),
+        (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+        (r#"{}"#, r#"{"a":{"bb":{"ccc":null}}}"#, r#"{"a":{"bb":{}}}"#),
+    ];
+
+    for (target, patch, result) in cases.iter() {
+        let domain = Domain::from_json(target)?;
+        domain.root().merge_patch_json(patch)?;
+        assert_eq!(&domain.root().to_json()?, result, "merging {} into {}", patch, target);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn changes_are_logged_individually() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"
+        {"db": {"host": "a", "port": 1, "user": "root"}, "cache": {"size": 1}}
+    "#)?;
+    let imported = *domain.log().txid_max.read().unwrap();
+
+    let spot = domain.navigate("/db")?.merge_patch_json(r#"
+        {"port": 2, "user": null, "host": "a", "pool": {"max": 5, "min": null}}
+    "#)?;
+    assert_eq!(spot.to_json()?, r#"{"host":"a","pool":{"max":5},"port":2}"#);
+
+    assert_eq!(
+        domain.log().to_json_patch(imported + 1..)?,
+        concat!(
+            r#"[{"op":"add","path":"/db/pool","value":{"max":5}},"#,
+            r#"{"op":"replace","path":"/db/port","value":2},"#,
+            r#"{"op":"remove","path":"/db/user"}]"#
+        )
+    );
+    assert_eq!(domain.navigate("/cache/size")?.to_i64(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn merge_node_value() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"b": 1, "c": 2}}"#)?;
+
+    let inner = MapValue::new()
+        .set_item("b".to_string(), Arc::new(NodeValue::None)).0
+        .set_item("d".to_string(), Arc::new(NodeValue::from(4))).0;
+    let patch = MapValue::new()
+        .set_item("a".to_string(), Arc::new(NodeValue::Map(inner))).0;
+
+    domain.root().merge_patch(NodeValue::Map(patch))?;
+    assert_eq!(domain.root().to_json()?, r#"{"a":{"c":2,"d":4}}"#);
+
+    Ok(())
+}
+
+#[test]
+fn deep_merge_strategies() -> Result<(), Error> {
+
+    let base = r#"{"name":"a","tags":["x","y"],"db":{"host":"h","ports":[1,2]}}"#;
+    let layer = Domain::from_json(r#"{"name":"b","tags":["z"],"db":{"ports":[3],"user":"u"}}"#)?;
+
+    let cases = [
+        (ListMerge::Append, ScalarMerge::KeepRight,
+            r#"{"db":{"host":"h","ports":[1,2,3],"user":"u"},"name":"b","tags":["x","y","z"]}"#),
+        (ListMerge::Replace, ScalarMerge::KeepLeft,
+            r#"{"db":{"host":"h","ports":[3],"user":"u"},"name":"a","tags":["z"]}"#),
+        (ListMerge::ByIndex, ScalarMerge::KeepRight,
+            r#"{"db":{"host":"h","ports":[3,2],"user":"u"},"name":"b","tags":["z","y"]}"#),
+    ];
+
+    for (lists, scalars, result) in cases.iter() {
+        let domain = Domain::from_json(base)?;
+        let strategy = MergeStrategy::new(*lists, *scalars);
+        let (spot, conflicts) = domain.root().deep_merge(&layer.root(), strategy)?;
+
+        assert_eq!(&spot.to_json()?, result);
+        assert_eq!(&domain.root().to_json()?, result);
+
+        let mut paths = conflicts.iter().map(|focus| focus.access_path()).collect::<Vec<String>>();
+        paths.sort();
+        match lists {
+            ListMerge::ByIndex => assert_eq!(paths, vec!["/db/ports#0", "/name", "/tags#0"]),
+            _ => assert_eq!(paths, vec!["/name"]),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn deep_merge_conflict_error() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a":{"b":1,"c":[1]},"d":true}"#)?;
+    let other = Domain::from_json(r#"{"a":{"b":{"x":1},"c":[2]},"d":true,"e":2}"#)?;
+    let txid = *domain.log().txid_max.read().unwrap();
+
+    let strategy = MergeStrategy::new(ListMerge::Append, ScalarMerge::Error);
+    match domain.root().deep_merge(&other.root(), strategy) {
+        Err(Error::MergeConflict { conflicts }) => {
+            let paths = conflicts.iter().map(|focus| focus.access_path()).collect::<Vec<String>>();
+            assert_eq!(paths, vec!["/a/b"]);
+        },
+        result => panic!("unexpected result: {:?}", result.map(|(spot, _)| spot.to_json())),
+    }
+
+    assert_eq!(domain.root().to_json()?, r#"{"a":{"b":1,"c":[1]},"d":true}"#);
+    assert_eq!(*domain.log().txid_max.read().unwrap(), txid);
+
+    let (spot, conflicts) = domain.navigate("/a/c")?.deep_merge(&other.navigate("/a/c")?, strategy)?;
+    assert!(conflicts.is_empty());
+    assert_eq!(spot.to_json()?, "[1,2]");
+
+    Ok(())
+}
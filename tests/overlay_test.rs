@@ -0,0 +1,66 @@
+use dcone::{Domain, Overlay};
+
+use dcone::Error;
+
+fn config() -> Result<Overlay, Error> {
+    let mut overlay = Overlay::new();
+    overlay.push_layer("defaults", Domain::from_json(r#"
+        {"db": {"host": "localhost", "port": 5432, "pool": 4}, "debug": "off"}
+    "#)?);
+    overlay.push_layer("site", Domain::from_json(r#"
+        {"db": {"host": "db.example.com"}}
+    "#)?);
+    overlay.push_layer("overrides", Domain::from_json(r#"{"debug": "on"}"#)?);
+    Ok(overlay)
+}
+
+#[test]
+fn resolve_top_down() -> Result<(), Error> {
+
+    let overlay = config()?;
+    assert_eq!(overlay.layer_names(), vec!["overrides", "site", "defaults"]);
+
+    let (layer, spot) = overlay.resolve("/db/host")?;
+    assert_eq!(layer, "site");
+    assert_eq!(spot.to_json()?, r#""db.example.com""#);
+
+    assert_eq!(overlay.source_of("/db/port")?, "defaults");
+    assert_eq!(overlay.source_of("/debug")?, "overrides");
+    assert_eq!(overlay.navigate("/db/pool")?.to_json()?, "4");
+
+    match overlay.navigate("/db/user") {
+        Err(Error::NoSuchItem { .. }) => {},
+        other => panic!("unexpected result: {:?}", other.map(|spot| spot.to_json())),
+    }
+    assert_eq!(overlay.layer("env").err(), Some(Error::NoSuchLayer("env".to_string())));
+
+    Ok(())
+}
+
+#[test]
+fn write_to_layer() -> Result<(), Error> {
+
+    let overlay = config()?;
+
+    overlay.navigate_in("overrides", "/")?.set_map_item("db")?;
+    overlay.navigate_in("overrides", "/db")?.set_item("port", 6432)?;
+
+    assert_eq!(overlay.source_of("/db/port")?, "overrides");
+    assert_eq!(overlay.navigate("/db/port")?.to_json()?, "6432");
+    assert_eq!(overlay.source_of("/db/host")?, "site");
+    assert_eq!(overlay.layer("defaults")?.navigate("/db/port")?.to_json()?, "5432");
+
+    overlay.navigate_in("site", "/db")?.remove("host")?;
+    assert_eq!(overlay.source_of("/db/host")?, "defaults");
+
+    Ok(())
+}
+
+#[test]
+fn empty_overlay() {
+
+    let overlay = Overlay::new();
+
+    assert_eq!(overlay.navigate("/db").err(), Some(Error::EmptyOverlay));
+    assert_eq!(Error::EmptyOverlay.to_string(), "The overlay has no layers");
+}
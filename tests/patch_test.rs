@@ -0,0 +1,144 @@
+use dcone::Domain;
+use dcone::focus::FocusLocator;
+
+use dcone::Error;
+
+#[test]
+fn apply_operations() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"
+        {"db": {"host": "a", "port": 1}, "tags": ["x", "y"], "old": {"k": 1}}
+    "#)?;
+
+    domain.root().apply_patch(r#"[
+        {"op": "replace", "path": "/db/host", "value": "b"},
+        {"op": "add", "path": "/db/user", "value": "admin"},
+        {"op": "add", "path": "/tags/1", "value": "w"},
+        {"op": "add", "path": "/tags/-", "value": "z"},
+        {"op": "remove", "path": "/tags/0"},
+        {"op": "move", "from": "/old", "path": "/db/extra"},
+        {"op": "copy", "from": "/db/port", "path": "/port"},
+        {"op": "test", "path": "/db/extra/k", "value": 1}
+    ]"#)?;
+
+    assert_eq!(
+        domain.root().to_json()?,
+        r#"{"db":{"extra":{"k":1},"host":"b","port":1,"user":"admin"},"port":1,"tags":["w","y","z"]}"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn relative_to_spot() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"b": {"c": 1}}, "a~/b": 0}"#)?;
+
+    let spot = domain.navigate("/a")?.apply_patch(r#"[
+        {"op": "replace", "path": "/b/c", "value": 2},
+        {"op": "add", "path": "/d", "value": [1, 2]}
+    ]"#)?;
+    assert_eq!(spot.to_json()?, r#"{"b":{"c":2},"d":[1,2]}"#);
+
+    domain.root().apply_patch(r#"[{"op": "replace", "path": "/a~0~1b", "value": 5}]"#)?;
+    assert_eq!(domain.navigate("/a/d#1")?.to_i64(), 2);
+    assert_eq!(domain.root().to_json()?, r#"{"a":{"b":{"c":2},"d":[1,2]},"a~/b":5}"#);
+
+    domain.navigate("/a/b")?.apply_patch(r#"[{"op": "replace", "path": "", "value": "whole"}]"#)?;
+    assert_eq!(domain.navigate("/a/b")?.to_string(), "whole");
+
+    Ok(())
+}
+
+#[test]
+fn all_or_nothing() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": 1, "b": [1, 2]}"#)?;
+    let before = domain.root();
+    let log_len = domain.log().log.read().unwrap().len();
+
+    let result = domain.root().apply_patch(r#"[
+        {"op": "replace", "path": "/a", "value": 2},
+        {"op": "remove", "path": "/b/0"},
+        {"op": "test", "path": "/b/0", "value": 1}
+    ]"#);
+
+    match result {
+        Err(Error::PatchTestFailed { focus }) => assert_eq!(focus.access_path(), "/b#0"),
+        _ => panic!("the test op should fail"),
+    }
+
+    assert!(domain.root().diff(&before).is_empty());
+    assert_eq!(domain.log().log.read().unwrap().len(), log_len);
+
+    Ok(())
+}
+
+#[test]
+fn failing_paths() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"b": 1}, "l": [0]}"#)?;
+
+    match domain.root().apply_patch(r#"[{"op": "remove", "path": "/a/x"}]"#) {
+        Err(Error::NoSuchItem { focus, access_key }) => {
+            assert_eq!(focus.access_path(), "/a");
+            assert_eq!(access_key, "x".into());
+        },
+        _ => panic!("removing a missing key should fail"),
+    }
+
+    match domain.root().apply_patch(r#"[{"op": "add", "path": "/l/5", "value": 1}]"#) {
+        Err(Error::NoSuchItem { focus, .. }) => assert_eq!(focus.access_path(), "/l"),
+        _ => panic!("adding beyond the end of a list should fail"),
+    }
+
+    match domain.root().apply_patch(r#"[{"op": "add", "path": "/l/01", "value": 1}]"#) {
+        Err(Error::WrongItemAccess { .. }) => {},
+        _ => panic!("leading zeros are not a valid index"),
+    }
+
+    match domain.root().apply_patch(r#"[{"op": "move", "from": "/a", "path": "/a/b/c"}]"#) {
+        Err(Error::InvalidPatch(_)) => {},
+        _ => panic!("a location cannot be moved into its child"),
+    }
+
+    match domain.root().apply_patch(r#"[{"op": "frobnicate", "path": "/a"}]"#) {
+        Err(Error::InvalidPatch(_)) => {},
+        _ => panic!("unknown op"),
+    }
+
+    assert_eq!(domain.root().to_json()?, r#"{"a":{"b":1},"l":[0]}"#);
+
+    Ok(())
+}
+
+#[test]
+fn export_history() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"db": {"port": 1}, "tags": ["x", "y"]}"#)?;
+    let imported = *domain.log().txid_max.read().unwrap();
+
+    domain.navigate("/db")?.set_item("port", 2)?.set_item("a/b", "c")?;
+    domain.navigate("/tags")?.push_item("z")?.remove(0)?.insert_item(-1, "w")?;
+    domain.root().remove("db")?;
+
+    assert_eq!(
+        domain.log().to_json_patch(imported + 1..)?,
+        concat!(
+            r#"[{"op":"replace","path":"/db/port","value":2},"#,
+            r#"{"op":"add","path":"/db/a~1b","value":"c"},"#,
+            r#"{"op":"add","path":"/tags/2","value":"z"},"#,
+            r#"{"op":"remove","path":"/tags/0"},"#,
+            r#"{"op":"add","path":"/tags/1","value":"w"},"#,
+            r#"{"op":"remove","path":"/db"}]"#
+        )
+    );
+
+    // 完整的历史可以在另一个domain上重放
+    let replica = Domain::new();
+    replica.root().apply_patch(&domain.log().to_json_patch(..)?)?;
+    assert!(replica.root().deep_eq(&domain.root()));
+    assert_eq!(replica.root().to_json()?, r#"{"tags":["y","w","z"]}"#);
+
+    Ok(())
+}
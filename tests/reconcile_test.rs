@@ -0,0 +1,230 @@
+use std::sync::Arc;
+use std::thread;
+
+use dcone::{Domain, Error, ListValue, NodeValue};
+
+#[test]
+fn sibling_keys_from_the_same_parent() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"x": 0, "y": 0}}"#)?;
+
+    let first = domain.navigate("/a")?;
+    let second = domain.navigate("/a")?;
+
+    first.set_item("x", 1)?;
+    second.set_item("y", 2)?.set_item("z", 3)?;
+
+    assert_eq!(domain.root().to_json()?, r#"{"a":{"x":1,"y":2,"z":3}}"#);
+
+    Ok(())
+}
+
+#[test]
+fn removals_and_nested_edits() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"b": {"c": 1}, "d": 1, "e": 1}}"#)?;
+
+    let first = domain.navigate("/a")?;
+    let second = domain.navigate("/a")?;
+    let nested = domain.navigate("/a/b")?;
+
+    first.remove("d")?;
+    second.set_item("e", 2)?;
+    nested.set_item("c", 2)?;
+
+    assert_eq!(domain.root().to_json()?, r#"{"a":{"b":{"c":2},"e":2}}"#);
+
+    Ok(())
+}
+
+#[test]
+fn list_indices_from_the_same_list() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"items": [1, 2, 3]}"#)?;
+
+    let first = domain.navigate("/items")?;
+    let second = domain.navigate("/items")?;
+
+    first.set_item(0, 10)?;
+    second.set_item(2, 30)?;
+
+    assert_eq!(domain.root().to_json()?, r#"{"items":[10,2,30]}"#);
+
+    Ok(())
+}
+
+#[test]
+fn same_key_keeps_the_later_write() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"x": 0}}"#)?;
+
+    let first = domain.navigate("/a")?;
+    let second = domain.navigate("/a")?;
+
+    first.set_item("x", 1)?;
+    second.set_item("x", 2)?;
+
+    assert_eq!(domain.navigate("/a/x")?.to_json()?, "2");
+
+    Ok(())
+}
+
+#[test]
+fn stale_root_keeps_committed_writes() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": 1}"#)?;
+
+    let stale = domain.root();
+    domain.transaction(|tx| {
+        tx.root().set_item("b", 2)?;
+        Ok(())
+    })?;
+    stale.set_item("c", 3)?;
+
+    assert_eq!(domain.root().to_json()?, r#"{"a":1,"b":2,"c":3}"#);
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_writers_on_siblings() -> Result<(), Error> {
+
+    let domain = Arc::new(Domain::from_json(r#"{"counters": {}}"#)?);
+
+    let workers = (0..4).map(|worker| {
+        let domain = domain.clone();
+        let counters = domain.navigate("/counters").unwrap();
+        thread::spawn(move || {
+            counters.set_item(format!("w{}", worker).as_str(), worker).unwrap();
+            domain.root();
+        })
+    }).collect::<Vec<_>>();
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    assert_eq!(domain.navigate("/counters")?.to_json()?, r#"{"w0":0,"w1":1,"w2":2,"w3":3}"#);
+
+    Ok(())
+}
+
+/// 从日志导出的补丁重放后与domain一致
+fn assert_replayable(domain: &Domain) -> Result<(), Error> {
+    let replay = Domain::new();
+    replay.root().apply_patch(&domain.log().to_json_patch(..)?)?;
+    assert_eq!(replay.root().to_json()?, domain.root().to_json()?);
+    Ok(())
+}
+
+#[test]
+fn list_pushes_from_the_same_list() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"items": [1, 2, 3]}"#)?;
+
+    let first = domain.navigate("/items")?;
+    let second = domain.navigate("/items")?;
+    let third = domain.navigate("/items")?;
+
+    first.push_item(4)?;
+    second.push_item(5)?;
+    third.set_item(0, 10)?;
+
+    assert_eq!(domain.navigate("/items")?.to_json()?, "[10,2,3,4,5]");
+    assert_replayable(&domain)
+}
+
+#[test]
+fn list_inserts_and_removes_from_the_same_list() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"items": ["a", "b", "c", "d"]}"#)?;
+
+    let first = domain.navigate("/items")?;
+    let second = domain.navigate("/items")?;
+    let third = domain.navigate("/items")?;
+    let fourth = domain.navigate("/items")?;
+
+    first.insert_item(1, "x")?;
+    // 删除写入时所见的"c"，而不是当前在下标2的"b"
+    second.remove(2)?;
+    // 插入到写入时所见的"d"之前
+    third.insert_item(3, "y")?;
+    // "c"已被删除，不再重复删除
+    fourth.remove(2)?.set_item(0, "A")?;
+
+    assert_eq!(domain.navigate("/items")?.to_json()?, r#"["A","x","b","y","d"]"#);
+    assert_replayable(&domain)
+}
+
+#[test]
+fn list_edits_in_one_transaction() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"items": [1, 2, 3]}"#)?;
+
+    domain.transaction(|tx| {
+        let first = tx.navigate("/items")?;
+        let second = tx.navigate("/items")?;
+
+        let first = first.push_item(4)?;
+        second.remove(0)?;
+        first.push_item(5)?.insert_item(0, 0)?;
+        Ok(())
+    })?;
+
+    assert_eq!(domain.navigate("/items")?.to_json()?, "[0,2,3,4,5]");
+    assert_replayable(&domain)
+}
+
+#[test]
+fn concurrent_pushes_on_one_list() -> Result<(), Error> {
+
+    let domain = Arc::new(Domain::from_json(r#"{"items": []}"#)?);
+
+    let workers = (0..4).map(|worker| {
+        let domain = domain.clone();
+        thread::spawn(move || -> Result<(), Error> {
+            for _ in 0..25 {
+                domain.navigate("/items")?.push_item(worker)?;
+            }
+            Ok(())
+        })
+    }).collect::<Vec<_>>();
+
+    for worker in workers {
+        worker.join().unwrap()?;
+    }
+
+    let items = domain.navigate("/items")?;
+    assert_eq!(items.len()?, 4 * 25);
+    for worker in 0..4 {
+        let count = items.children()?
+            .filter(|(_, item)| item.as_i64().ok() == Some(worker))
+            .count();
+        assert_eq!(count, 25);
+    }
+    assert_replayable(&domain)
+}
+
+#[test]
+fn list_with_the_same_node_twice() -> Result<(), Error> {
+
+    // 同一个节点在列表中出现两次
+    let item = Arc::new(NodeValue::from("a"));
+    let list = ListValue::new().push(item.clone()).push(item).push(Arc::new(NodeValue::from("b")));
+    let domain = Domain::from_json(r#"{"items": []}"#)?;
+    domain.root().set_item("items", NodeValue::List(list))?;
+
+    let first = domain.navigate("/items")?;
+    let second = domain.navigate("/items")?;
+    let third = domain.navigate("/items")?;
+
+    first.insert_item(0, "z")?;
+    // 修改的是写入时所见的第二个"a"，删除的是第一个
+    second.set_item(1, "x")?;
+    assert_eq!(domain.navigate("/items")?.to_json()?, r#"["z","a","x","b"]"#);
+
+    third.remove(0)?;
+    assert_eq!(domain.navigate("/items")?.to_json()?, r#"["z","x","b"]"#);
+
+    assert_replayable(&domain)
+}
@@ -0,0 +1,169 @@
+use std::sync::mpsc::TryRecvError;
+use std::thread;
+
+use dcone::{Domain, Error, NodeEvent, StreamEvent};
+use dcone::focus::FocusLocator;
+
+fn event_path(item: &StreamEvent) -> String {
+    match item {
+        StreamEvent::Event(event) => event.focus().access_path(),
+        StreamEvent::Lagged(missed) => format!("lagged {}", missed),
+    }
+}
+
+#[test]
+fn committed_events_in_order() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": 1, "items": []}"#)?;
+    let events = domain.event_stream(16);
+
+    domain.root().set_item("a", 2)?;
+    domain.transaction(|tx| {
+        tx.navigate("/items")?.push_item(1)?.push_item(2)?;
+        Ok(())
+    })?;
+
+    let items = events.try_iter().collect::<Vec<_>>();
+    assert_eq!(
+        items.iter().map(event_path).collect::<Vec<_>>(),
+        vec!["/a", "/items#0", "/items#1"]
+    );
+
+    let txids = items.iter().map(|item| match item {
+        StreamEvent::Event(event) => event.txid(),
+        StreamEvent::Lagged(_) => unreachable!(),
+    }).collect::<Vec<_>>();
+    assert!(txids[0] < txids[1]);
+    assert_eq!(txids[1], txids[2]);
+
+    assert!(matches!(&items[0], StreamEvent::Event(NodeEvent::ValueUpdated { .. })));
+
+    Ok(())
+}
+
+#[test]
+fn rolled_back_events_are_not_sent() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": 1}"#)?;
+    let events = domain.event_stream(16);
+
+    let result: Result<(), Error> = domain.transaction(|tx| {
+        tx.root().set_item("a", 2)?;
+        tx.navigate("/missing")?;
+        Ok(())
+    });
+    assert!(result.is_err());
+
+    assert!(matches!(events.try_recv(), Err(TryRecvError::Empty)));
+
+    Ok(())
+}
+
+#[test]
+fn slow_consumers_are_told_they_lagged() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"n": 0}"#)?;
+    let events = domain.event_stream(2);
+
+    for n in 1..=5 {
+        domain.root().set_item("n", n)?;
+    }
+
+    // 缓冲区只容纳前两个事件，取完之后收到丢弃的个数
+    assert_eq!(
+        events.try_iter().map(|e| event_path(&e)).collect::<Vec<_>>(),
+        vec!["/n", "/n", "lagged 3"]
+    );
+
+    domain.root().set_item("n", 6)?;
+    domain.root().set_item("n", 7)?;
+    assert_eq!(events.try_iter().map(|e| event_path(&e)).collect::<Vec<_>>(), vec!["/n", "/n"]);
+
+    // 丢弃之后的事件也被丢弃，直到缓冲区中的事件被取完，因此不会乱序
+    for n in 8..=10 {
+        domain.root().set_item("n", n)?;
+    }
+    assert_eq!(event_path(&events.try_recv().unwrap()), "/n");
+    domain.root().set_item("n", 11)?;
+    assert_eq!(
+        events.try_iter().map(|e| event_path(&e)).collect::<Vec<_>>(),
+        vec!["/n", "lagged 2"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn consumer_on_another_thread() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"n": 0}"#)?;
+    let events = domain.event_stream(128);
+
+    let consumer = thread::spawn(move || {
+        events.iter().take(10).filter(|item| matches!(item, StreamEvent::Event(_))).count()
+    });
+
+    for n in 1..=10 {
+        domain.root().set_item("n", n)?;
+    }
+
+    assert_eq!(consumer.join().unwrap(), 10);
+
+    Ok(())
+}
+
+#[test]
+fn dropped_receivers_stop_receiving() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"n": 0}"#)?;
+
+    drop(domain.event_stream(1));
+    assert_eq!(domain.subscriber_count(), 0);
+
+    domain.root().set_item("n", 1)?;
+    domain.root().set_item("n", 2)?;
+
+    assert_eq!(domain.root().to_json()?, r#"{"n":2}"#);
+
+    Ok(())
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn futures_stream() -> Result<(), Error> {
+    use futures::StreamExt;
+
+    let domain = Domain::from_json(r#"{"n": 0}"#)?;
+    let events = domain.event_stream_async(2);
+
+    for n in 1..=4 {
+        domain.root().set_item("n", n)?;
+    }
+    drop(domain);
+
+    let items = futures::executor::block_on(events.collect::<Vec<_>>());
+    assert_eq!(items.iter().map(event_path).collect::<Vec<_>>(), vec!["/n", "/n", "lagged 2"]);
+
+    Ok(())
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn futures_stream_resumes_after_lag() -> Result<(), Error> {
+    use futures::StreamExt;
+    use futures::executor::block_on;
+
+    let domain = Domain::from_json(r#"{"n": 0}"#)?;
+    let mut events = domain.event_stream_async(1);
+
+    domain.root().set_item("n", 1)?;
+    domain.root().set_item("n", 2)?;
+    assert_eq!(block_on(events.next()).as_ref().map(event_path), Some("/n".to_string()));
+    assert_eq!(block_on(events.next()).as_ref().map(event_path), Some("lagged 1".to_string()));
+
+    // 取走Lagged之后的事件照常送出
+    domain.root().set_item("n", 3)?;
+    assert_eq!(block_on(events.next()).as_ref().map(event_path), Some("/n".to_string()));
+
+    Ok(())
+}
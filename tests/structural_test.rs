@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use dcone::{Domain, NodeValue, StructuralKey};
+
+use dcone::Error;
+
+#[test]
+fn deep_eq_and_digest() -> Result<(), Error> {
+
+    let d1 = Domain::from_json(r#"{"a": {"x": 1, "y": [1, 2.5, "s"]}, "b": null}"#)?;
+    let d2 = Domain::from_json(r#"{"b": null, "a": {"y": [1, 2.5, "s"], "x": 1}}"#)?;
+    let d3 = Domain::from_json(r#"{"a": {"x": 1, "y": [1, 2.5, "t"]}, "b": null}"#)?;
+
+    assert!(d1.root().deep_eq(&d2.root()));
+    assert!(!d1.root().deep_eq(&d3.root()));
+    assert_eq!(d1.root().content_digest(), d2.root().content_digest());
+    assert_ne!(d1.root().content_digest(), d3.root().content_digest());
+
+    assert!(d1.navigate("/a/x")?.deep_eq(&d3.navigate("/a/x")?));
+    assert!(!d1.navigate("/a/y")?.deep_eq(&d3.navigate("/a/y")?));
+
+    // 整数和浮点数是不同的值
+    let int_domain = Domain::from_json("[1]")?;
+    let float_domain = Domain::from_json("[1.0]")?;
+    assert!(!int_domain.root().deep_eq(&float_domain.root()));
+
+    // StructuralKey按内容判等
+    assert!(d1.root().structural_key() == d2.root().structural_key());
+
+    // NodeValue本身仍按指针判等，内容相同的两个节点不相等
+    let node = d1.root().get_node().clone();
+    assert_eq!(node.as_ref(), d1.root().get_node().as_ref());
+    assert_ne!(node.as_ref(), d2.root().get_node().as_ref());
+
+    Ok(())
+}
+
+#[test]
+fn float_semantics() {
+
+    let nan = NodeValue::Float(f64::NAN);
+    assert!(nan.deep_eq(&NodeValue::Float(-f64::NAN)));
+    assert!(NodeValue::Float(0.0).deep_eq(&NodeValue::Float(-0.0)));
+    assert_eq!(NodeValue::Float(0.0).content_digest(), NodeValue::Float(-0.0).content_digest());
+    assert!(!NodeValue::Float(1.0).deep_eq(&NodeValue::Float(1.5)));
+}
+
+#[test]
+fn structural_key_in_set() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"[{"k": 1}, {"k": 2}, {"k": 1}, [1, 2], [1, 2]]"#)?;
+
+    let mut seen = HashSet::new();
+    for index in 0..5 {
+        seen.insert(domain.navigate(&format!("#{}", index))?.structural_key());
+    }
+    assert_eq!(seen.len(), 3);
+
+    assert!(!seen.contains(&StructuralKey::new(Arc::new(NodeValue::from(1)))));
+
+    Ok(())
+}
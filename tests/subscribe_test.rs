@@ -0,0 +1,239 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use dcone::{Change, Domain, Error, NodeEvent, NodeValue};
+use dcone::focus::FocusLocator;
+
+fn recorder() -> (Arc<Mutex<Vec<Change>>>, impl Fn(&Change) + Send + Sync + 'static) {
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let sink = changes.clone();
+    (changes, move |change: &Change| sink.lock().unwrap().push(change.clone()))
+}
+
+fn paths(changes: &Arc<Mutex<Vec<Change>>>) -> Vec<String> {
+    changes.lock().unwrap().iter()
+        .map(|change| change.focus().access_path())
+        .collect()
+}
+
+fn node(json: &str) -> Result<NodeValue, Error> {
+    Ok(Domain::from_json(json)?.root().get_node().as_ref().clone())
+}
+
+#[test]
+fn changes_beneath_the_focus() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"b": 1}, "c": 2}"#)?;
+
+    let (changes, callback) = recorder();
+    let _subscription = domain.subscribe("/a", callback)?;
+
+    domain.navigate("/a")?.set_item("b", 10)?;
+    domain.root().set_item("c", 20)?;
+    domain.navigate("/a")?.set_item("d", "x")?;
+    domain.navigate("/a")?.remove("d")?;
+
+    assert_eq!(paths(&changes), vec!["/a/b", "/a/d", "/a/d"]);
+
+    let changes = changes.lock().unwrap();
+
+    assert!(matches!(changes[0].event(), NodeEvent::ValueUpdated { .. }));
+    assert!(matches!(changes[0].old_value().map(|v| v.as_ref()), Some(NodeValue::Integer(1))));
+    assert!(matches!(changes[0].new_value().map(|v| v.as_ref()), Some(NodeValue::Integer(10))));
+
+    assert!(matches!(changes[1].event(), NodeEvent::ValueCreated { .. }));
+    assert!(changes[1].old_value().is_none());
+
+    assert!(matches!(changes[2].event(), NodeEvent::ValueDeleted { .. }));
+    assert!(matches!(changes[2].old_value().map(|v| v.as_ref()), Some(NodeValue::String(s)) if s == "x"));
+    assert!(changes[2].new_value().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn wildcard_segments() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{
+        "services": {
+            "web": {"replicas": 1, "image": "web:1"},
+            "db": {"replicas": 1, "image": "db:1"}
+        }
+    }"#)?;
+
+    let (changes, callback) = recorder();
+    let _subscription = domain.subscribe("/services/*/replicas", callback)?;
+
+    domain.navigate("/services/web")?.set_item("replicas", 3)?;
+    domain.navigate("/services/db")?.set_item("image", "db:2")?;
+    domain.navigate("/services/db")?.set_item("replicas", 2)?;
+
+    assert_eq!(paths(&changes), vec!["/services/web/replicas", "/services/db/replicas"]);
+
+    Ok(())
+}
+
+#[test]
+fn subscribe_to_a_spot_focus() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"items": [1, 2]}"#)?;
+
+    let items = domain.navigate("/items")?;
+    let (changes, callback) = recorder();
+    let _subscription = domain.subscribe(items.get_focus(), callback)?;
+
+    domain.navigate("/items")?.push_item(3)?;
+    domain.navigate("/items")?.remove(0)?;
+
+    assert_eq!(paths(&changes), vec!["/items#2", "/items#0"]);
+
+    let changes = changes.lock().unwrap();
+    assert!(matches!(changes[0].event(), NodeEvent::ListItemInserted { .. }));
+    assert!(matches!(changes[1].event(), NodeEvent::ListItemDeleted { .. }));
+
+    Ok(())
+}
+
+#[test]
+fn transactions_deliver_on_commit() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": 1, "b": 2}"#)?;
+
+    let (changes, callback) = recorder();
+    let _subscription = domain.subscribe("/", callback)?;
+
+    let delivered = changes.clone();
+    domain.transaction(|tx| {
+        tx.root().set_item("a", 10)?.set_item("b", 20)?;
+
+        // 提交之前不送出
+        assert!(delivered.lock().unwrap().is_empty());
+        Ok(())
+    })?;
+
+    assert_eq!(paths(&changes), vec!["/a", "/b"]);
+    let txids = changes.lock().unwrap().iter().map(|c| c.txid()).collect::<Vec<_>>();
+    assert_eq!(txids[0], txids[1]);
+
+    let result: Result<(), Error> = domain.transaction(|tx| {
+        tx.root().set_item("a", 100)?;
+        tx.navigate("/missing")?;
+        Ok(())
+    });
+    assert!(result.is_err());
+
+    // 回滚的修改不送出
+    assert_eq!(paths(&changes).len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn rolled_back_savepoints_are_not_delivered() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": 1, "b": 2}"#)?;
+
+    let (changes, callback) = recorder();
+    let _subscription = domain.subscribe("/", callback)?;
+
+    domain.transaction(|tx| {
+        tx.root().set_item("a", 10)?;
+        let savepoint = tx.savepoint();
+        tx.root().set_item("b", 20)?;
+        tx.rollback_to(savepoint)?;
+        Ok(())
+    })?;
+
+    assert_eq!(paths(&changes), vec!["/a"]);
+
+    Ok(())
+}
+
+#[test]
+fn dropping_the_guard_unsubscribes() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": 1}"#)?;
+
+    let (changes, callback) = recorder();
+    let subscription = domain.subscribe("/a", callback)?;
+
+    domain.root().set_item("a", 2)?;
+    drop(subscription);
+    domain.root().set_item("a", 3)?;
+
+    assert_eq!(paths(&changes), vec!["/a"]);
+
+    Ok(())
+}
+
+#[test]
+fn callbacks_may_write() -> Result<(), Error> {
+
+    let domain = Arc::new(Domain::from_json(r#"{"count": 0, "seen": 0}"#)?);
+
+    let target = domain.clone();
+    let _subscription = domain.subscribe("/count", move |change| {
+        if let Some(NodeValue::Integer(count)) = change.new_value().map(|v| v.as_ref()) {
+            target.root().set_item("seen", *count).unwrap();
+        }
+    })?;
+
+    domain.root().set_item("count", 5)?;
+
+    assert_eq!(domain.root().to_json()?, r#"{"count":5,"seen":5}"#);
+
+    Ok(())
+}
+
+#[test]
+fn ancestors_replaced() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"services": {"web": {"replicas": 1}}, "name": "a"}"#)?;
+
+    let (changes, callback) = recorder();
+    let _subscription = domain.subscribe("/services/*/replicas", callback)?;
+
+    domain.root().set_item("services", node(r#"{"web": {"replicas": 2}}"#)?)?;
+    domain.root().set_json(r#"{"services": {}}"#)?;
+    domain.navigate("/services")?.set_item("db", node(r#"{"replicas": 3}"#)?)?;
+    domain.root().remove("services")?;
+
+    // 兄弟位置的修改不送出
+    domain.root().set_item("name", "b")?;
+
+    assert_eq!(paths(&changes), vec!["/services", "/", "/services/db", "/services"]);
+
+    let changes = changes.lock().unwrap();
+    assert!(matches!(changes[1].event(), NodeEvent::RootUpdated { .. }));
+    assert!(matches!(changes[3].event(), NodeEvent::ValueDeleted { .. }));
+
+    Ok(())
+}
+
+#[test]
+fn panicking_callback() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"n": 0}"#)?;
+
+    let (changes, callback) = recorder();
+    let _recorder = domain.subscribe("/n", callback)?;
+    let failed = AtomicBool::new(false);
+    let _panicking = domain.subscribe("/n", move |_: &Change| {
+        if !failed.swap(true, Ordering::SeqCst) {
+            panic!("callback failed");
+        }
+    })?;
+
+    // 回调的panic从写入处抛出
+    let result = panic::catch_unwind(AssertUnwindSafe(|| domain.root().set_item("n", 1)));
+    assert!(result.is_err());
+
+    // 之后的写入仍然送出通知
+    domain.root().set_item("n", 2)?;
+    domain.root().set_item("n", 3)?;
+
+    assert_eq!(paths(&changes), vec!["/n", "/n", "/n"]);
+
+    Ok(())
+}
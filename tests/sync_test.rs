@@ -0,0 +1,148 @@
+use std::sync::{Arc, Barrier, mpsc};
+use std::thread;
+
+use dcone::{Domain, Error, Spot};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn domain_and_spot_are_send_sync() {
+    assert_send_sync::<Domain>();
+    assert_send_sync::<Spot>();
+}
+
+#[test]
+fn concurrent_writers() -> Result<(), Error> {
+
+    let domain = Arc::new(Domain::from_json(r#"{"workers": {"0": {}, "1": {}, "2": {}, "3": {}}}"#)?);
+
+    let handles = (0..4).map(|worker| {
+        let domain = domain.clone();
+        thread::spawn(move || -> Result<(), Error> {
+            let mut spot = domain.navigate(&format!("/workers/{}", worker))?;
+            for n in 0..50 {
+                spot = spot.set_item(format!("k{}", n), n)?;
+                // 读取只取得已提交的根节点，不会等待写入
+                domain.root();
+            }
+            Ok(())
+        })
+    }).collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap()?;
+    }
+
+    for worker in 0..4 {
+        let spot = domain.navigate(&format!("/workers/{}", worker))?;
+        assert_eq!(spot.len()?, 50);
+        assert_eq!(spot.navigate("k49")?.as_i64()?, 49);
+    }
+    assert_eq!(*domain.log().txid_max.read().unwrap(), 1 + 4 * 50);
+
+    Ok(())
+}
+
+#[test]
+fn readers_see_committed_root() -> Result<(), Error> {
+
+    let domain = Arc::new(Domain::from_json(r#"{"a": 1, "b": {"c": 1}}"#)?);
+    let in_transaction = Arc::new(Barrier::new(2));
+    let observed = Arc::new(Barrier::new(2));
+
+    let writer = {
+        let domain = domain.clone();
+        let in_transaction = in_transaction.clone();
+        let observed = observed.clone();
+        thread::spawn(move || {
+            domain.transaction(|tx| {
+                tx.root().set_item("a", 2)?;
+                assert_eq!(tx.navigate("/a")?.as_i64()?, 2);
+
+                in_transaction.wait();
+                observed.wait();
+                Ok(tx.txid())
+            })
+        })
+    };
+
+    in_transaction.wait();
+
+    // 事务未提交时只能读到之前的根节点，读取不会阻塞
+    assert_eq!(domain.root().to_json()?, r#"{"a":1,"b":{"c":1}}"#);
+    assert_eq!(domain.navigate("/a")?.as_i64()?, 1);
+
+    // 其他线程的写入排在事务之后
+    let (sender, receiver) = mpsc::channel();
+    let blocked = {
+        let domain = domain.clone();
+        thread::spawn(move || -> Result<(), Error> {
+            domain.navigate("/b")?.set_item("c", 2)?;
+            sender.send(*domain.log().txid_max.read().unwrap()).unwrap();
+            Ok(())
+        })
+    };
+    assert!(receiver.recv_timeout(std::time::Duration::from_millis(50)).is_err());
+
+    observed.wait();
+    let txid = writer.join().unwrap()?;
+    blocked.join().unwrap()?;
+
+    assert_eq!(receiver.recv().unwrap(), txid + 1);
+    assert_eq!(domain.root().to_json()?, r#"{"a":2,"b":{"c":2}}"#);
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_writers_on_one_parent() -> Result<(), Error> {
+
+    let domain = Arc::new(Domain::from_json(r#"{"shared": {}}"#)?);
+    let start = Arc::new(Barrier::new(5));
+
+    let writers = (0..4).map(|worker| {
+        let domain = domain.clone();
+        let start = start.clone();
+        thread::spawn(move || -> Result<(), Error> {
+            // 各线程从同一个旧版本出发，写入同一个父节点
+            let mut shared = domain.navigate("/shared")?;
+            start.wait();
+
+            for n in 0..50 {
+                shared = shared.set_item(format!("{}-{}", worker, n), n)?;
+                domain.navigate("/shared")?.set_item(format!("last-{}", worker), n)?;
+            }
+            Ok(())
+        })
+    }).collect::<Vec<_>>();
+
+    // 读取与写入同时进行，读到的总是某个已提交的版本
+    let reader = {
+        let domain = domain.clone();
+        let start = start.clone();
+        thread::spawn(move || -> Result<(), Error> {
+            start.wait();
+            let mut last = 0;
+            for _ in 0..200 {
+                let root = domain.root();
+                let len = root.navigate("shared")?.len()?;
+                assert!(len >= last);
+                last = len;
+            }
+            Ok(())
+        })
+    };
+
+    for writer in writers {
+        writer.join().unwrap()?;
+    }
+    reader.join().unwrap()?;
+
+    assert_eq!(domain.navigate("/shared")?.len()?, 4 * 50 + 4);
+    for worker in 0..4 {
+        assert_eq!(domain.navigate(&format!("/shared/{}-49", worker))?.as_i64()?, 49);
+        assert_eq!(domain.navigate(&format!("/shared/last-{}", worker))?.as_i64()?, 49);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use dcone::{Domain, Error};
+use dcone::focus::AccessKey;
+
+fn txids(domain: &Domain) -> Vec<u64> {
+    let mut txids = Vec::new();
+    domain.log().foreach(|_, event| txids.push(event.txid()));
+    txids.dedup();
+    txids.reverse();
+    txids
+}
+
+#[test]
+fn writes_share_one_txid() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"db": {"host": "a", "port": 1}, "tags": []}"#)?;
+    let before = *domain.log().txid_max.read().unwrap();
+
+    let txid = domain.transaction(|tx| {
+        tx.navigate("/db")?.set_item("host", "b")?.set_item("port", 2)?;
+        tx.navigate("/tags")?.push_item("x")?.push_item("y")?;
+        tx.root().set_item("name", "web")?;
+        Ok(tx.txid())
+    })?;
+
+    assert_eq!(txid, before + 1);
+    assert_eq!(*domain.log().txid_max.read().unwrap(), txid);
+    assert_eq!(txids(&domain).last(), Some(&txid));
+    assert!(txids(&domain).iter().all(|id| *id <= before || *id == txid));
+
+    assert_eq!(
+        domain.root().to_json()?,
+        r#"{"db":{"host":"b","port":2},"name":"web","tags":["x","y"]}"#
+    );
+
+    // 没有写入的事务不占用txid
+    domain.transaction(|tx| tx.navigate("/db/port")?.as_i64())?;
+    assert_eq!(*domain.log().txid_max.read().unwrap(), txid);
+
+    Ok(())
+}
+
+#[test]
+fn rollback_on_error() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"db": {"host": "a", "port": 1}, "tags": ["x"]}"#)?;
+    domain.navigate("/db")?.set_item("port", 2)?;
+    // 事务开始前的写入会先合并到根节点
+    domain.root();
+
+    let txid_max = *domain.log().txid_max.read().unwrap();
+    let log_len = domain.log().log.read().unwrap().len();
+
+    let result: Result<(), Error> = domain.transaction(|tx| {
+        tx.navigate("/db")?.set_item("host", "b")?;
+        tx.navigate("/tags")?.push_item("y")?;
+        assert_eq!(tx.navigate("/db/host")?.as_str()?, "b");
+
+        tx.navigate("/tags")?.remove(5)?;
+        Ok(())
+    });
+
+    match result {
+        Err(Error::NoSuchItem { access_key, .. }) => assert_eq!(access_key, AccessKey::Index(5)),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    assert_eq!(*domain.log().txid_max.read().unwrap(), txid_max);
+    assert_eq!(domain.log().log.read().unwrap().len(), log_len);
+    assert!(domain.log().pending.read().unwrap().is_empty());
+    assert_eq!(domain.root().to_json()?, r#"{"db":{"host":"a","port":2},"tags":["x"]}"#);
+
+    Ok(())
+}
+
+#[test]
+fn nested_transaction_rolls_back_alone() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": 1, "b": 1}"#)?;
+
+    domain.transaction(|tx| {
+        tx.root().set_item("a", 2)?;
+
+        let inner: Result<(), Error> = domain.transaction(|inner| {
+            assert_eq!(inner.txid(), tx.txid());
+            inner.root().set_item("b", 2)?;
+            inner.navigate("/missing").map(|_| ())
+        });
+        assert!(inner.is_err());
+
+        domain.transaction(|inner| inner.root().set_item("c", 3).map(|_| ()))
+    })?;
+
+    assert_eq!(domain.root().to_json()?, r#"{"a":2,"b":1,"c":3}"#);
+
+    Ok(())
+}
+
+#[test]
+fn savepoints() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"rows": []}"#)?;
+
+    domain.transaction(|tx| {
+        tx.navigate("/rows")?.push_item(1)?;
+
+        let step = tx.savepoint();
+        tx.navigate("/rows")?.push_item(2)?;
+        let inner = tx.savepoint();
+        tx.navigate("/rows")?.push_item(3)?;
+        tx.release(inner)?;
+        tx.rollback_to(step)?;
+        assert_eq!(tx.navigate("/rows")?.to_json()?, "[1]");
+
+        let step = tx.savepoint();
+        tx.navigate("/rows")?.push_item(4)?;
+        let inner = tx.savepoint();
+        tx.root().set_item("tmp", "x")?;
+        tx.rollback_to(inner)?;
+        tx.release(step)?;
+        Ok(())
+    })?;
+
+    assert_eq!(domain.root().to_json()?, r#"{"rows":[1,4]}"#);
+
+    // 日志里只有提交的修改
+    let txid = *domain.log().txid_max.read().unwrap();
+    assert_eq!(
+        domain.log().to_json_patch(txid..)?,
+        r#"[{"op":"add","path":"/rows/0","value":1},{"op":"add","path":"/rows/1","value":4}]"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn stale_savepoint() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": 1}"#)?;
+
+    let result = domain.transaction(|tx| {
+        let outer = tx.savepoint();
+        let inner = tx.savepoint();
+        tx.root().set_item("a", 2)?;
+        tx.rollback_to(outer)?;
+        tx.release(inner)
+    });
+
+    assert_eq!(result, Err(Error::NoSuchSavepoint(2)));
+    assert_eq!(domain.root().to_json()?, r#"{"a":1}"#);
+
+    Ok(())
+}
+
+#[test]
+fn rollback_on_panic() -> Result<(), Error> {
+    use std::panic::{self, AssertUnwindSafe};
+
+    let domain = Domain::from_json(r#"{"a": 1, "b": 1}"#)?;
+    let txid_max = *domain.log().txid_max.read().unwrap();
+    let log_len = domain.log().log.read().unwrap().len();
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        domain.transaction(|tx| -> Result<(), Error> {
+            tx.root().set_item("a", 2)?;
+            panic!("failed in the middle of a transaction");
+        })
+    }));
+    assert!(result.is_err());
+
+    assert_eq!(*domain.log().txid_max.read().unwrap(), txid_max);
+    assert_eq!(domain.log().log.read().unwrap().len(), log_len);
+    assert!(domain.log().transaction.read().unwrap().is_none());
+    assert_eq!(domain.root().to_json()?, r#"{"a":1,"b":1}"#);
+
+    // 写入锁已释放，其他线程可以继续写入
+    std::thread::scope(|scope| {
+        scope.spawn(|| domain.root().set_item("b", 2).map(|_| ())).join().unwrap()
+    })?;
+    assert_eq!(domain.root().to_json()?, r#"{"a":1,"b":2}"#);
+
+    Ok(())
+}
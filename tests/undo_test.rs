@@ -0,0 +1,96 @@
+use dcone::{Domain, Error};
+
+#[test]
+fn undo_and_redo_transactions() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"title": "a", "items": []}"#)?;
+
+    domain.transaction(|tx| {
+        tx.root().set_item("title", "b")?;
+        tx.navigate("/items")?.push_item(1)?.push_item(2)?;
+        Ok(())
+    })?;
+    domain.navigate("/items")?.push_item(3)?;
+
+    assert_eq!(domain.root().to_json()?, r#"{"items":[1,2,3],"title":"b"}"#);
+
+    assert!(domain.undo()?);
+    assert_eq!(domain.root().to_json()?, r#"{"items":[1,2],"title":"b"}"#);
+    assert!(domain.undo()?);
+    assert_eq!(domain.root().to_json()?, r#"{"items":[],"title":"a"}"#);
+
+    assert!(domain.redo()?);
+    assert_eq!(domain.root().to_json()?, r#"{"items":[1,2],"title":"b"}"#);
+    assert!(domain.can_redo());
+
+    // 撤销和重做作为新的变更记入日志
+    let txid = *domain.log().txid_max.read().unwrap();
+    assert_eq!(
+        domain.log().to_json_patch(txid..)?,
+        r#"[{"op":"replace","path":"","value":{"items":[1,2],"title":"b"}}]"#
+    );
+
+    assert!(domain.redo()?);
+    assert_eq!(domain.root().to_json()?, r#"{"items":[1,2,3],"title":"b"}"#);
+    assert!(!domain.redo()?);
+
+    Ok(())
+}
+
+#[test]
+fn divergent_edit_clears_redo() -> Result<(), Error> {
+
+    let domain = Domain::new();
+    assert!(!domain.can_undo());
+
+    domain.root().set_json(r#"{"n": 1}"#)?;
+    domain.root().set_item("n", 2)?;
+
+    assert!(domain.undo()?);
+    assert_eq!(domain.navigate("/n")?.as_i64()?, 1);
+    assert!(domain.can_redo());
+
+    domain.root().set_item("n", 5)?;
+    assert!(!domain.can_redo());
+    assert!(!domain.redo()?);
+
+    assert!(domain.undo()?);
+    assert_eq!(domain.navigate("/n")?.as_i64()?, 1);
+    assert!(domain.undo()?);
+    assert!(domain.root().is_none());
+    assert!(!domain.undo()?);
+
+    Ok(())
+}
+
+#[test]
+fn undo_is_bounded_by_retained_versions() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"n": 0}"#)?;
+    domain.retain_versions(3);
+
+    for n in 1..=5 {
+        domain.root().set_item("n", n)?;
+    }
+
+    // 只能撤销到保留的最旧版本
+    assert!(domain.undo()?);
+    assert!(domain.undo()?);
+    assert_eq!(domain.root().to_json()?, r#"{"n":3}"#);
+    assert!(!domain.undo()?);
+
+    // 重做后重新记入的撤销也受限制
+    assert!(domain.redo()?);
+    assert!(domain.redo()?);
+    domain.retain_versions(2);
+    assert!(domain.undo()?);
+    assert_eq!(domain.root().to_json()?, r#"{"n":4}"#);
+    assert!(!domain.can_undo());
+
+    // 撤销之后的写入照常记入历史
+    domain.root().set_item("n", 10)?;
+    assert!(domain.undo()?);
+    assert_eq!(domain.root().to_json()?, r#"{"n":4}"#);
+
+    Ok(())
+}
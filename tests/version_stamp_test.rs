@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use dcone::{Domain, Error};
+
+const DOC: &str = r#"{
+    "a": {"b": {"c": 1}, "d": 1},
+    "e": {"f": 1},
+    "list": [{"v": 0}, {"v": 1}]
+}"#;
+
+#[test]
+fn writes_bump_the_path_and_its_ancestors() -> Result<(), Error> {
+
+    let domain = Domain::from_json(DOC)?;
+    let before = domain.version_of("/a/b/c")?;
+
+    domain.navigate("/a/b")?.set_item("c", 2)?;
+
+    let root = domain.root().version();
+    assert!(domain.version_of("/a/b/c")? > before);
+    assert_eq!(domain.version_of("/a/b")?, domain.version_of("/a/b/c")?);
+    assert_eq!(domain.version_of("/a")?, domain.version_of("/a/b/c")?);
+    assert_eq!(root, domain.version_of("/a/b/c")?);
+
+    // 兄弟分支不受影响
+    assert_eq!(domain.version_of("/a/d")?, before);
+    assert_eq!(domain.version_of("/e/f")?, before);
+
+    Ok(())
+}
+
+#[test]
+fn replacing_a_node_bumps_its_descendants() -> Result<(), Error> {
+
+    let domain = Domain::from_json(DOC)?;
+    let before = domain.version_of("/a/b/c")?;
+
+    let subtree = Domain::from_json(r#"{"b": {"c": 9}}"#)?.root().get_node().as_ref().clone();
+    domain.root().set_item("a", subtree)?;
+
+    let version = domain.version_of("/a")?;
+    assert!(version > before);
+    assert_eq!(domain.version_of("/a/b/c")?, version);
+
+    domain.navigate("/e")?.set_item("f", 2)?;
+    assert_eq!(domain.version_of("/a")?, version);
+
+    domain.navigate("/list")?.insert_item(0, 9)?;
+    let list = domain.version_of("/list")?;
+    assert_eq!(domain.version_of("/list#1/v")?, list);
+    assert_eq!(domain.version_of("/list#2/v")?, list);
+
+    Ok(())
+}
+
+#[test]
+fn spot_version_is_an_etag() -> Result<(), Error> {
+
+    let domain = Domain::from_json(DOC)?;
+
+    let spot = domain.navigate("/a")?;
+    let etag = spot.version();
+
+    // If-Match：版本未变时才写入
+    domain.navigate("/e")?.set_item("f", 3)?;
+    assert_eq!(domain.version_of("/a")?, etag);
+
+    domain.navigate("/a/b")?.set_item("c", 3)?;
+    assert_ne!(domain.version_of("/a")?, etag);
+
+    // spot的版本对应它取得时的节点
+    assert_eq!(spot.version(), etag);
+    assert_eq!(domain.navigate("/a")?.version(), domain.version_of("/a")?);
+
+    Ok(())
+}
+
+#[test]
+fn rolled_back_writes_keep_the_version() -> Result<(), Error> {
+
+    let domain = Domain::from_json(DOC)?;
+    let before = domain.version_of("/a/b/c")?;
+
+    let result: Result<(), Error> = domain.transaction(|tx| {
+        tx.navigate("/a/b")?.set_item("c", 5)?;
+        assert!(tx.navigate("/a/b/c")?.version() > before);
+        tx.navigate("/missing")?;
+        Ok(())
+    });
+    assert!(result.is_err());
+
+    assert_eq!(domain.version_of("/a/b/c")?, before);
+    assert_eq!(domain.version_of("/a")?, before);
+
+    domain.transaction(|tx| {
+        tx.navigate("/a/b")?.set_item("c", 5)?;
+        tx.navigate("/e")?.set_item("f", 5)?;
+        Ok(())
+    })?;
+    assert_eq!(domain.version_of("/a/b/c")?, domain.version_of("/e/f")?);
+    assert!(domain.version_of("/a/b/c")? > before);
+
+    Ok(())
+}
+
+#[test]
+fn deleted_paths_have_no_version() -> Result<(), Error> {
+
+    let domain = Domain::from_json(DOC)?;
+    domain.navigate("/a")?.remove("d")?;
+
+    assert!(domain.version_of("/a/d").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn stamps_of_removed_paths_are_released() -> Result<(), Error> {
+
+    let domain = Domain::from_json(DOC)?;
+    domain.navigate("/a/b")?.set_item("c", 2)?;
+    domain.navigate("/list#1")?.set_item("v", 2)?;
+
+    let deleted = Arc::downgrade(domain.navigate("/a/b/c")?.get_focus());
+    let replaced = Arc::downgrade(domain.navigate("/list#1/v")?.get_focus());
+
+    domain.root().remove("a")?;
+    domain.root().set_item("list", 0)?;
+
+    // 日志中的事件也持有focus
+    domain.compact_log(*domain.log().txid_max.read().unwrap())?;
+    assert!(deleted.upgrade().is_none());
+    assert!(replaced.upgrade().is_none());
+
+    assert!(domain.version_of("/a").is_err());
+    domain.root().set_item("a", 1)?;
+    assert_eq!(domain.version_of("/a")?, *domain.log().txid_max.read().unwrap());
+
+    Ok(())
+}
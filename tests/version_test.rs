@@ -0,0 +1,121 @@
+use dcone::{Domain, Error};
+use dcone::focus::AccessKey;
+
+#[test]
+fn snapshot_is_pinned() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"db": {"port": 1}}"#)?;
+    domain.navigate("/db")?.set_item("port", 2)?;
+
+    let snapshot = domain.snapshot();
+    assert_eq!(snapshot.txid(), *domain.log().txid_max.read().unwrap());
+
+    domain.navigate("/db")?.set_item("port", 3)?;
+    domain.root().set_item("name", "web")?;
+
+    assert_eq!(snapshot.navigate("/db/port")?.as_i64()?, 2);
+    assert_eq!(snapshot.root().to_json()?, r#"{"db":{"port":2}}"#);
+    assert_eq!(domain.root().to_json()?, r#"{"db":{"port":3},"name":"web"}"#);
+
+    // 快照只能读取
+    let keys = snapshot.root().children()?
+        .map(|(access_key, child)| (access_key, child.is_map()))
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec![(AccessKey::from("db"), true)]);
+
+    let port = snapshot.navigate("/db/port")?;
+    assert!(port.is_integer() && port.is_number());
+    assert!(!port.is_bool() && !port.is_float() && !port.is_string());
+
+    Ok(())
+}
+
+#[test]
+fn checkout_versions() -> Result<(), Error> {
+
+    let domain = Domain::new();
+    domain.root().set_json(r#"{"n": 0}"#)?;
+    for n in 1..=3 {
+        domain.root().set_item("n", n)?;
+    }
+    let txid = domain.transaction(|tx| {
+        tx.root().set_item("n", 10)?.set_item("m", 20)?;
+        Ok(tx.txid())
+    })?;
+
+    assert_eq!(domain.versions(), vec![0, 1, 2, 3, 4, txid]);
+
+    assert!(domain.checkout(0)?.root().is_none());
+    assert_eq!(domain.checkout(1)?.root().to_json()?, r#"{"n":0}"#);
+    assert_eq!(domain.checkout(3)?.navigate("/n")?.as_i64()?, 2);
+    assert_eq!(domain.checkout(txid)?.root().to_json()?, r#"{"m":20,"n":10}"#);
+    assert_eq!(domain.checkout(99).err(), Some(Error::NoSuchVersion(99)));
+
+    // 事务中的txid还没有版本，读取的是它之前最近的版本
+    domain.transaction(|tx| {
+        let snapshot = domain.checkout(tx.txid())?;
+        assert_eq!(snapshot.txid(), txid);
+        assert_eq!(snapshot.navigate("/n")?.as_i64()?, 10);
+        Ok(())
+    })?;
+
+    domain.retain_versions(2);
+    assert_eq!(domain.versions(), vec![4, txid]);
+    assert!(domain.checkout(1).is_err());
+
+    domain.root().set_item("n", 11)?;
+    assert_eq!(domain.versions(), vec![txid, txid + 1]);
+
+    Ok(())
+}
+
+#[test]
+fn nested_writes_keep_their_txid() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"a": {"b": {"c": 1}}}"#)?;
+    domain.navigate("/a/b")?.set_item("c", 2)?;
+    let txid = *domain.log().txid_max.read().unwrap();
+
+    // 向上合并产生的内部事件沿用写入的txid
+    assert_eq!(domain.versions(), vec![0, 1, txid]);
+    assert_eq!(*domain.log().txid_max.read().unwrap(), txid);
+    assert_eq!(domain.checkout(txid)?.navigate("/a/b/c")?.as_i64()?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn nested_rollback_keeps_outer_pending() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"db": {"host": "a", "port": 1}}"#)?;
+    let versions = domain.versions();
+
+    // 外层事务中的写入在提交前一直待合并
+    domain.transaction(|outer| {
+        outer.navigate("/db")?.set_item("port", 2)?;
+
+        let txid_max = *domain.log().txid_max.read().unwrap();
+        let log_len = domain.log().log.read().unwrap().len();
+        let pending = domain.log().pending.read().unwrap().len();
+        assert!(pending > 0);
+
+        let result: Result<(), Error> = domain.transaction(|tx| {
+            tx.navigate("/db")?.set_item("host", "b")?;
+            tx.navigate("/db")?.remove("user")?;
+            Ok(())
+        });
+        assert!(result.is_err());
+
+        // 内层事务回滚后，外层的待合并更新原样保留
+        assert_eq!(*domain.log().txid_max.read().unwrap(), txid_max);
+        assert_eq!(domain.log().log.read().unwrap().len(), log_len);
+        assert_eq!(domain.log().pending.read().unwrap().len(), pending);
+        Ok(())
+    })?;
+
+    // 回滚的写入不产生版本
+    assert_eq!(domain.root().to_json()?, r#"{"db":{"host":"a","port":2}}"#);
+    assert_eq!(domain.versions().len(), versions.len() + 1);
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use dcone::{Domain, Spot, Visitor};
+use dcone::focus::FocusLocator;
+
+use dcone::Error;
+
+fn paths<I: Iterator<Item = Spot>>(spots: I) -> Vec<String> {
+    spots.map(|spot| spot.get_focus().access_path()).collect()
+}
+
+#[test]
+fn walk_orders() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"{"b": [1, {"c": 2}], "a": "x"}"#)?;
+
+    assert_eq!(paths(domain.root().walk()), vec!["/", "/a", "/b", "/b#0", "/b#1", "/b#1/c"]);
+    assert_eq!(paths(domain.root().walk().post_order()), vec!["/a", "/b#0", "/b#1/c", "/b#1", "/b", "/"]);
+    assert_eq!(paths(domain.navigate("/a")?.walk()), vec!["/a"]);
+
+    let mut walk = domain.root().walk();
+    let mut visited = Vec::new();
+    while let Some(spot) = walk.next() {
+        if spot.is_list() {
+            walk.skip_children();
+        }
+        visited.push(spot.get_focus().access_path());
+    }
+    assert_eq!(visited, vec!["/", "/a", "/b"]);
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct Audit {
+    events: Vec<String>,
+    leaves: usize,
+}
+
+impl Visitor for Audit {
+    fn enter_map(&mut self, spot: &Spot) -> bool {
+        self.events.push(format!("map {}", spot.get_focus().access_path()));
+        spot.get_focus().access_path() != "/secret"
+    }
+
+    fn enter_list(&mut self, spot: &Spot) -> bool {
+        self.events.push(format!("list {}", spot.get_focus().access_path()));
+        true
+    }
+
+    fn leaf(&mut self, _spot: &Spot) {
+        self.leaves += 1;
+    }
+
+    fn exit(&mut self, spot: &Spot) {
+        self.events.push(format!("exit {}", spot.get_focus().access_path()));
+    }
+}
+
+#[test]
+fn visitor_with_pruning() -> Result<(), Error> {
+
+    let domain = Domain::from_json(r#"
+        {"secret": {"key": "k", "token": "t"}, "ports": [80, 443], "name": "web"}
+    "#)?;
+
+    let mut audit = Audit::default();
+    domain.root().accept(&mut audit);
+
+    assert_eq!(audit.leaves, 3);
+    assert_eq!(audit.events, vec![
+        "map /", "list /ports", "exit /ports", "map /secret", "exit /",
+    ]);
+
+    Ok(())
+}